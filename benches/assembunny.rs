@@ -0,0 +1,48 @@
+//! Compares the assembunny backends on the puzzle inputs in `./data`, by
+//! running every part with a `backend` parameter (days 12, 23 and 25) with
+//! each of them. Run with `cargo bench --bench assembunny`.
+
+use advent_of_code_2016::registry::{ParamOverride, Params, Part};
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 20;
+
+fn median(part: &Part, input: &str, backend: &str) -> Result<Duration> {
+    let backend: ParamOverride = format!("backend={}", backend).parse()?;
+    let params = Params::for_part(part, [&backend]);
+    let mut timings = Vec::with_capacity(RUNS);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        (part.solver)(input, &params)?;
+        timings.push(start.elapsed());
+    }
+    timings.sort();
+    Ok(timings[RUNS / 2])
+}
+
+fn main() -> Result<()> {
+    let mut parts: Vec<&Part> = inventory::iter::<Part>
+        .into_iter()
+        .filter(|part| part.has_param("backend"))
+        .collect();
+    parts.sort_by_key(|part| (part.day, part.part_name));
+
+    println!("median of {} runs", RUNS);
+    for part in parts {
+        let path = format!("data/{}.txt", part.module_name);
+        let input =
+            std::fs::read_to_string(&path).with_context(|| format!("cannot read {}", path))?;
+        let interpreter = median(part, &input, "interpreter")?;
+        let compiled = median(part, &input, "compiled")?;
+        println!(
+            "{} {:<3}  interpreter {:>10.2?}  compiled {:>10.2?}  {:.1}x",
+            part.module_name,
+            part.part_name,
+            interpreter,
+            compiled,
+            interpreter.as_secs_f64() / compiled.as_secs_f64()
+        );
+    }
+    Ok(())
+}
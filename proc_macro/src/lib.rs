@@ -1,187 +1,272 @@
-#![feature(
-    proc_macro_diagnostic,
-    proc_macro_quote,
-    proc_macro_span,
-    proc_macro_hygiene
-)]
+#![cfg_attr(feature = "nightly", feature(proc_macro_diagnostic))]
 
 extern crate proc_macro;
-use proc_macro::{
-    quote, Delimiter, Diagnostic, Ident, Level, Literal, Punct, Spacing, Span, TokenStream,
-    TokenTree,
-};
-use std::convert::Into;
-use std::iter::FromIterator;
+use proc_macro2::{Group, Ident, Literal, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned};
 
-fn generate_module_list_impl(token_stream: TokenStream) -> Result<TokenStream, Diagnostic> {
-    // parse input
-    struct Module {
-        name: Ident,
-        parts: Vec<Ident>,
-        parser: Option<Ident>,
+// An error at a location in the attribute or the annotated item.
+struct Error {
+    span: Span,
+    message: String,
+}
+
+fn error<S: Into<String>>(span: Span, message: S) -> Error {
+    Error {
+        span,
+        message: message.into(),
     }
-    let mut modules: Vec<Module> = Vec::new();
-    let mut tokens = token_stream.into_iter().peekable();
+}
 
-    let const_ident = match tokens.next() {
-        Some(TokenTree::Ident(const_ident)) => const_ident,
-        Some(token) => return Err(token.span().error("expected constant name")),
-        None => return Err(Diagnostic::new(Level::Error, "expected arguments")),
-    };
-    match tokens.next() {
-        Some(TokenTree::Punct(punct))
-            if punct.as_char() == ';' && punct.spacing() == Spacing::Alone => {}
-        Some(token) => return Err(token.span().error("expected ;")),
-        None => {}
+impl Error {
+    // Reports the error, and still emits the item so that uses of the
+    // annotated function do not produce follow-up errors.
+    #[cfg(feature = "nightly")]
+    fn emit(self, item: TokenStream) -> TokenStream {
+        self.span.unwrap().error(self.message).emit();
+        item
     }
 
-    while let Some(module_ident) = tokens.next() {
-        if let TokenTree::Ident(module_ident) = module_ident {
-            module_ident.span().warning("found name");
-            let group = match tokens.next() {
-                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => group,
-                Some(ident) => return Err(ident.span().error("expected brackets [ ]")),
-                None => return Err(Diagnostic::new(Level::Error, "unexpected end of input")),
-            };
+    #[cfg(not(feature = "nightly"))]
+    fn emit(self, mut item: TokenStream) -> TokenStream {
+        let message = self.message;
+        item.extend(quote_spanned!(self.span=> compile_error!(#message);));
+        item
+    }
+}
 
-            let mut group_tokens = group.stream().into_iter();
-            let mut parts = Vec::new();
-            while let Some(part_ident) = group_tokens.next() {
-                if let TokenTree::Ident(part_ident) = part_ident {
-                    parts.push(part_ident);
-                } else {
-                    return Err(part_ident.span().error("expected part name"));
-                }
-                match group_tokens.next() {
-                    None => {}
-                    Some(TokenTree::Punct(punct))
-                        if punct.as_char() == ',' && punct.spacing() == Spacing::Alone => {}
-                    Some(token) => return Err(token.span().error("expected ,")),
-                }
+// Parses `name = <literal>, ...`, returning each name with its default value
+// as it would be written on the command line.
+fn parse_params(group: &Group) -> Result<Vec<(String, String)>, Error> {
+    let mut params = Vec::new();
+    let mut tokens = group.stream().into_iter().peekable();
+    while let Some(name) = tokens.next() {
+        let name = match name {
+            TokenTree::Ident(name) => name,
+            token => return Err(error(token.span(), "expected a parameter name")),
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            _ => {
+                return Err(error(
+                    name.span(),
+                    format!("expected = <default> after {}", name),
+                ))
             }
-
-            let parser = match tokens.peek().clone() {
-                Some(TokenTree::Punct(punct))
-                    if punct.as_char() == ':' && punct.spacing() == Spacing::Alone =>
-                {
-                    let punct = tokens.next().unwrap();
-                    if let Some(TokenTree::Ident(input_ident)) = tokens.next() {
-                        Some(input_ident)
-                    } else {
-                        return Err(punct.span().error("expected parser name after"));
+        }
+        let mut default = String::new();
+        if let Some(TokenTree::Punct(punct)) = tokens.peek() {
+            if punct.as_char() == '-' {
+                default.push('-');
+                tokens.next();
+            }
+        }
+        match tokens.next() {
+            Some(TokenTree::Literal(literal)) => {
+                let literal = literal.to_string();
+                if literal.starts_with('"') {
+                    if literal.contains('\\') {
+                        return Err(error(name.span(), "escapes are not supported in defaults"));
                     }
+                    default.push_str(&literal[1..literal.len() - 1]);
+                } else {
+                    default.push_str(&literal);
                 }
-                _ => None,
-            };
+            }
+            _ => {
+                return Err(error(
+                    name.span(),
+                    format!("expected a literal default value for {}", name),
+                ))
+            }
+        }
+        params.push((name.to_string(), default));
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            None => break,
+            Some(token) => return Err(error(token.span(), "expected ,")),
+        }
+    }
+    Ok(params)
+}
 
-            modules.push(Module {
-                name: module_ident,
-                parts,
-                parser,
-            });
+fn aoc_impl(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+    // parse attribute arguments: day = <n>, part = <1|2|both>, parser = <path>,
+    // params(<name> = <default>, ...)
+    let mut day: Option<(u32, Span)> = None;
+    let mut parts: Option<Vec<u32>> = None;
+    let mut parser: Option<TokenStream> = None;
+    let mut params = Vec::new();
+    let mut tokens = attr.into_iter().peekable();
 
+    while let Some(key) = tokens.next() {
+        let key = match key {
+            TokenTree::Ident(key) => key,
+            token => return Err(error(token.span(), "expected day, part, parser or params")),
+        };
+        if key == "params" {
             match tokens.next() {
+                Some(TokenTree::Group(group)) => params = parse_params(&group)?,
+                _ => {
+                    return Err(error(
+                        key.span(),
+                        "expected (<name> = <default>, ...) after params",
+                    ))
+                }
+            }
+            match tokens.next() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
                 None => {}
-                Some(TokenTree::Punct(punct))
-                    if punct.as_char() == ',' && punct.spacing() == Spacing::Alone => {}
-                Some(token) => {
-                    return Err(token.span().error("expected ,"));
+                Some(token) => return Err(error(token.span(), "expected ,")),
+            }
+            continue;
+        }
+        match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+            Some(token) => return Err(error(token.span(), "expected =")),
+            None => return Err(error(key.span(), format!("expected = after {}", key))),
+        }
+        let mut value = Vec::new();
+        while let Some(token) = tokens.peek() {
+            match token {
+                TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                    tokens.next();
+                    break;
                 }
+                _ => value.push(tokens.next().unwrap()),
             }
-        } else {
-            return Err(module_ident.span().error("expected module name"));
         }
-    }
+        if value.is_empty() {
+            return Err(error(key.span(), format!("expected a value after {}", key)));
+        }
 
-    let mut tokens: Vec<TokenTree> = Vec::new();
-    // emit module imports
-    for module in &modules {
-        tokens.push(Ident::new("mod", Span::call_site()).into());
-        tokens.push(module.name.clone().into());
-        tokens.push(Punct::new(';', Spacing::Alone).into());
+        match key.to_string().as_str() {
+            "day" => match value.as_slice() {
+                [TokenTree::Literal(literal)] => match literal.to_string().parse::<u32>() {
+                    Ok(nr @ 1..=25) => day = Some((nr, literal.span())),
+                    _ => return Err(error(literal.span(), "day must be in 1..=25")),
+                },
+                _ => return Err(error(key.span(), "expected a day number after day")),
+            },
+            "part" => match value.as_slice() {
+                [TokenTree::Literal(literal)] => match literal.to_string().as_str() {
+                    "1" => parts = Some(vec![1]),
+                    "2" => parts = Some(vec![2]),
+                    _ => return Err(error(literal.span(), "part must be 1, 2 or both")),
+                },
+                [TokenTree::Ident(ident)] if ident == "both" => parts = Some(vec![1, 2]),
+                _ => return Err(error(key.span(), "expected 1, 2 or both after part")),
+            },
+            "parser" => parser = Some(value.into_iter().collect()),
+            _ => {
+                return Err(error(
+                    key.span(),
+                    "unknown argument, expected day, part, parser or params",
+                ))
+            }
+        }
     }
+    let (day, day_span) = day.ok_or_else(|| error(Span::call_site(), "missing day = <n>"))?;
+    let parts = parts.ok_or_else(|| error(Span::call_site(), "missing part = <1|2|both>"))?;
 
-    // emit table
-    let mut table_tokens = TokenStream::new();
-    for module in modules {
-        let module_name: TokenTree = Literal::string(&module.name.to_string()).into();
-        // emit parts
-        let mut part_tokens = TokenStream::new();
-        for part in &module.parts {
-            let part_name: TokenTree = Literal::string(&part.to_string()).into();
-            let call_part = TokenStream::from_iter(vec![
-                TokenTree::Ident(module.name.clone()),
-                Punct::new(':', Spacing::Joint).into(),
-                Punct::new(':', Spacing::Joint).into(),
-                part.clone().into(),
-            ]);
-            match module.parser.clone() {
-                Some(parser) => {
-                    let call_parser = TokenStream::from_iter(vec![
-                        TokenTree::Ident(module.name.clone()),
-                        Punct::new(':', Spacing::Joint).into(),
-                        Punct::new(':', Spacing::Joint).into(),
-                        parser.clone().into(),
-                    ]);
-                    part_tokens.extend(quote!(
-                        ($part_name, |input: &str| -> Result<String> {
-                            use ::anyhow::anyhow;
-                            let input = input.trim();
-                            let input = $call_parser(input)
-                                .map_err(|err| anyhow!("parse error {:?}", err))
-                                .and_then(|(remainder, v)| {
-                                    if remainder == "" {
-                                        Ok(v)
-                                    } else {
-                                        Err(anyhow!("input partially parsed, remainder: {:#?}", remainder))
-                                    }
-                                })?;
-                            Ok(format!("{}", $call_part(input)?))
-                        }),
-                    ))
-                }
-                None => {
-                    part_tokens.extend(quote!(
-                        ($part_name, |input: &str| -> Result<String> {
-                            let input = input.trim();
-                            Ok(format!("{}", $call_part(input)?))
-                        }),
-                    ));
-                }
+    // find the name of the annotated function
+    let mut item_tokens = item.clone().into_iter();
+    let part_ident = loop {
+        match item_tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident == "fn" => match item_tokens.next() {
+                Some(TokenTree::Ident(name)) => break name,
+                _ => return Err(error(ident.span(), "expected function name")),
+            },
+            Some(_) => {}
+            None => {
+                return Err(error(
+                    Span::call_site(),
+                    "#[aoc] can only be applied to functions",
+                ))
             }
         }
+    };
+
+    let mut tokens = item;
 
-        table_tokens.extend(quote!(
-            ($module_name, &[
-                $part_tokens
-            ]),
+    // claim the slots, duplicates are conflicting trait implementations
+    let day_literal = Literal::u32_unsuffixed(day);
+    for &part in &parts {
+        let part_literal = Literal::u32_unsuffixed(part);
+        tokens.extend(quote_spanned!(day_span=>
+            impl crate::registry::Slot<#day_literal, #part_literal>
+                for crate::registry::Registry {}
         ));
     }
 
-    // emit DAY_LIST
-    tokens.push(Ident::new("const", Span::call_site()).into());
-    tokens.push(const_ident.into());
-    tokens.push(Punct::new(':', Spacing::Alone).into());
-    tokens.extend(
-        quote!(&[(&'static str, &[(&'static str, fn(&str) -> anyhow::Result<String>)])] = {
-        use std::fmt::Write;
-        use anyhow::Result;
-
-        &[
-            $table_tokens
-        ]
-    };),
-    );
+    // register a solver that reads the parameters, parses the input and
+    // formats the answer, parameters are passed after the input
+    let param_idents: Vec<Ident> = params
+        .iter()
+        .map(|(name, _)| format_ident!("{}", name))
+        .collect();
+    let param_names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+    let param_defaults: Vec<&str> = params.iter().map(|(_, default)| default.as_str()).collect();
+    let call_part = quote!(|input| #part_ident(input #(, #param_idents)*));
+    let solve = match parser {
+        Some(parser) => quote!(crate::registry::solve_parsed(input, #parser, #call_part)),
+        None => quote!(crate::registry::solve_raw(input, #call_part)),
+    };
+    let module_name = format!("day{:02}", day);
+    let part_name = part_ident.to_string();
+    let part_list = parts.iter().map(|&part| Literal::u32_unsuffixed(part));
+    let into_answer = if parts.len() > 1 {
+        quote!(crate::registry::Answer::both)
+    } else {
+        quote!(crate::registry::Answer::single)
+    };
+    tokens.extend(quote!(
+        const _: () = {
+            #[allow(unused_variables)]
+            fn solver(
+                input: &str,
+                params: &crate::registry::Params,
+            ) -> ::anyhow::Result<crate::registry::Answer> {
+                #(let #param_idents = params.get(#param_names, #param_defaults)?;)*
+                #solve.map(#into_answer)
+            }
+            ::inventory::submit! {
+                crate::registry::Part {
+                    module_name: #module_name,
+                    part_name: #part_name,
+                    day: #day_literal,
+                    parts: &[#(#part_list),*],
+                    params: &[#(crate::registry::Param {
+                        name: #param_names,
+                        default: #param_defaults,
+                    }),*],
+                    solver,
+                }
+            }
+        };
+    ));
 
-    let tokens = TokenStream::from_iter(tokens);
-    // println!("{}", tokens);
     Ok(tokens)
 }
 
-#[proc_macro]
-pub fn generate_module_list(token_stream: TokenStream) -> TokenStream {
-    generate_module_list_impl(token_stream).unwrap_or_else(|diag| {
-        diag.emit();
-        TokenStream::new()
-    })
+/// Registers a part with the runner.
+///
+/// `#[aoc(day = 7, part = 2, parser = parse)]` registers the annotated function
+/// as part 2 of day 7, and runs it on the output of `parse`. A function that
+/// solves both parts uses `part = both`, and functions taking the raw input
+/// omit the parser.
+///
+/// `params(rows = 40)` declares named parameters with their defaults, which
+/// can be overridden per input or from the command line. They are parsed with
+/// `FromStr` and passed to the function after the input, in declaration order.
+#[proc_macro_attribute]
+pub fn aoc(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let item = TokenStream::from(item);
+    match aoc_impl(attr.into(), item.clone()) {
+        Ok(tokens) => tokens,
+        Err(err) => err.emit(item),
+    }
+    .into()
 }
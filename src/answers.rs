@@ -0,0 +1,247 @@
+use crate::inputs::DEFAULT_INPUT;
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const DEFAULT_PATH: &str = "./data/answers.txt";
+
+/// Known good answers, keyed by module, input and part name.
+///
+/// The file contains one answer per line, as `<module> <part> <answer>` for
+/// the default input, or `<module>/<input> <part> <answer>` for named inputs.
+/// Answers spanning multiple lines have their newlines escaped as `\n`, and
+/// backslashes as `\\`. Empty lines and lines starting with `#` are ignored,
+/// and kept in place when the answers are saved again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    entries: BTreeMap<Key, String>,
+    // The lines of the file in order, answers recorded later at the end
+    lines: Vec<Line>,
+}
+
+type Key = (String, String, String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Text(String),
+    Answer(Key),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// The answer matches the recorded answer.
+    Pass,
+    /// The answer differs from the recorded answer, or the part failed.
+    Fail { expected: String },
+    /// There is no recorded answer for this part.
+    New,
+}
+
+fn escape(answer: &str) -> String {
+    answer.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(answer: &str) -> Result<String> {
+    let mut out = String::with_capacity(answer.len());
+    let mut chars = answer.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            other => {
+                return Err(anyhow!(
+                    "invalid escape sequence \\{}",
+                    other.unwrap_or(' ')
+                ))
+            }
+        }
+    }
+    Ok(out)
+}
+
+impl Answers {
+    /// Loads the answers from a file, a missing file counts as no answers.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                Answers::parse(&contents).with_context(|| format!("in {}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(err) => Err(err).with_context(|| format!("cannot read {}", path.display())),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut answers = Answers::default();
+        for (line_idx, line) in s.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                answers.lines.push(Line::Text(line.to_owned()));
+                continue;
+            }
+            let mut fields = line.splitn(3, ' ');
+            let (module_name, part_name, answer) =
+                match (fields.next(), fields.next(), fields.next()) {
+                    (Some(module_name), Some(part_name), Some(answer)) => {
+                        (module_name, part_name, answer)
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "line {}: expected <module> <part> <answer>",
+                            line_idx + 1
+                        ))
+                    }
+                };
+            let answer = unescape(answer).with_context(|| format!("line {}", line_idx + 1))?;
+            let (module_name, input_name) = module_name
+                .split_once('/')
+                .unwrap_or((module_name, DEFAULT_INPUT));
+            answers.insert(module_name, input_name, part_name, answer);
+        }
+        Ok(answers)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_string())
+            .with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    fn key(module_name: &str, input_name: &str, part_name: &str) -> Key {
+        (
+            module_name.to_owned(),
+            input_name.to_owned(),
+            part_name.to_owned(),
+        )
+    }
+
+    pub fn get(&self, module_name: &str, input_name: &str, part_name: &str) -> Option<&str> {
+        self.entries
+            .get(&Answers::key(module_name, input_name, part_name))
+            .map(String::as_str)
+    }
+
+    pub fn insert(&mut self, module_name: &str, input_name: &str, part_name: &str, answer: String) {
+        let key = Answers::key(module_name, input_name, part_name);
+        if self.entries.insert(key.clone(), answer).is_none() {
+            self.lines.push(Line::Answer(key));
+        }
+    }
+
+    /// Compares an answer against the recorded one, `None` meaning the part
+    /// did not produce an answer.
+    pub fn verdict(
+        &self,
+        module_name: &str,
+        input_name: &str,
+        part_name: &str,
+        answer: Option<&str>,
+    ) -> Verdict {
+        match (self.get(module_name, input_name, part_name), answer) {
+            (None, _) => Verdict::New,
+            (Some(expected), Some(answer)) if expected == answer => Verdict::Pass,
+            (Some(expected), _) => Verdict::Fail {
+                expected: expected.to_owned(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Answers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in &self.lines {
+            let key = match line {
+                Line::Text(text) => {
+                    writeln!(f, "{}", text)?;
+                    continue;
+                }
+                Line::Answer(key) => key,
+            };
+            let (module_name, input_name, part_name) = key;
+            let answer = &self.entries[key];
+            if input_name == DEFAULT_INPUT {
+                writeln!(f, "{} {} {}", module_name, part_name, escape(answer))?;
+            } else {
+                let answer = escape(answer);
+                writeln!(f, "{}/{} {} {}", module_name, input_name, part_name, answer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Verdict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::Pass => "pass",
+            Verdict::Fail { .. } => "fail",
+            Verdict::New => "new",
+        }
+    }
+}
+
+#[test]
+fn answers() -> Result<()> {
+    let mut answers = Answers::parse(
+        "\
+# comment
+day01 pt1 299
+day01/alice pt1 12
+day08 pts pt1 121\\npt2 a\\\\b
+",
+    )?;
+    assert_eq!(answers.get("day01", "default", "pt1"), Some("299"));
+    assert_eq!(answers.get("day01", "alice", "pt1"), Some("12"));
+    assert_eq!(answers.get("day01", "bob", "pt1"), None);
+    assert_eq!(
+        answers.get("day08", "default", "pts"),
+        Some("pt1 121\npt2 a\\b")
+    );
+    assert_eq!(answers.get("day01", "default", "pt2"), None);
+
+    assert_eq!(
+        answers.verdict("day01", "default", "pt1", Some("299")),
+        Verdict::Pass
+    );
+    assert_eq!(
+        answers.verdict("day01", "default", "pt1", Some("300")),
+        Verdict::Fail {
+            expected: "299".to_owned()
+        }
+    );
+    assert_eq!(
+        answers.verdict("day01", "default", "pt1", None),
+        Verdict::Fail {
+            expected: "299".to_owned()
+        }
+    );
+    assert_eq!(
+        answers.verdict("day01", "default", "pt2", Some("1")),
+        Verdict::New
+    );
+
+    answers.insert("day01", "default", "pt2", "181".to_owned());
+    answers.insert("day01", "bob", "pt2", "7".to_owned());
+    answers.insert("day01", "alice", "pt1", "13".to_owned());
+    assert_eq!(Answers::parse(&answers.to_string())?, answers);
+    // Comments stay in place, updated answers too, new ones come last
+    assert_eq!(
+        answers.to_string(),
+        "\
+# comment
+day01 pt1 299
+day01/alice pt1 13
+day08 pts pt1 121\\npt2 a\\\\b
+day01 pt2 181
+day01/bob pt2 7
+"
+    );
+
+    assert!(Answers::parse("day01 pt1").is_err());
+    assert!(Answers::parse("day01 pt1 \\x").is_err());
+
+    Ok(())
+}
@@ -1,22 +1,96 @@
 use anyhow::anyhow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::ops::{ControlFlow, Range};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Program {
-    pub instructions: Vec<Instruction>,
+    instructions: Vec<Instruction>,
     pub instruction_ptr: i64,
+    // Fused operation starting at each instruction, `None` if optimizations
+    // are disabled
+    fused: Option<Vec<Option<Fused>>>,
+    // Bytecode of every instruction, compiled when first run by the compiled
+    // backend
+    compiled: Option<Vec<Op>>,
+    dialect: Dialect,
+    // Values not yet read by `in` instructions
+    input: VecDeque<i64>,
 }
 
+// Number of steps between comparisons of the state in `Program::run_bounded`
+const LOOP_CHECK_INTERVAL: u64 = 1 << 12;
+
 pub const REGISTERY_SIZE: usize = 4;
-pub type Registers = [i64; 4];
+/// Register file of the puzzle dialect, any `&mut [i64]` of at least the
+/// dialect's number of registers works for other dialects.
+pub type Registers = [i64; REGISTERY_SIZE];
 
+/// Registers and instructions available to a program.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// Number of registers, named `a` onwards, at most 26.
+    pub registers: usize,
+    /// Allows the `add`, `mul`, `nop` and `in` extension instructions.
+    pub extensions: bool,
+}
+
+impl Dialect {
+    /// The instructions of days 12, 23 and 25, with registers `a` to `d`.
+    pub const PUZZLE: Dialect = Dialect {
+        registers: REGISTERY_SIZE,
+        extensions: false,
+    };
+    pub const EXTENDED: Dialect = Dialect {
+        registers: REGISTERY_SIZE,
+        extensions: true,
+    };
+
+    pub fn with_registers(self, registers: usize) -> Self {
+        Dialect { registers, ..self }
+    }
+
+    // Checks that the dialect has every register and instruction used by an
+    // instruction, the error is prefixed with its location by the caller
+    fn validate(&self, instruction: &Instruction) -> Result<(), String> {
+        if instruction.is_extension() && !self.extensions {
+            return Err(format!(
+                "{} is an extension instruction, which the puzzle dialect does not allow",
+                instruction
+            ));
+        }
+        match instruction
+            .registers()
+            .iter()
+            .flatten()
+            .copied()
+            .find(|&register| register >= self.registers)
+        {
+            Some(register) => Err(format!(
+                "register {} in {} is out of range, the program has {} registers",
+                Value::Register(register),
+                instruction,
+                self.registers
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect::PUZZLE
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Value {
     Constant(i64),
     Register(usize),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Instruction {
     Toggle(usize),
     Increment(usize),
@@ -24,53 +98,453 @@ pub enum Instruction {
     Out(Value),
     Copy(Value, Value),
     JumpIfNotZero(Value, Value),
+    /// Extension, `add x y` adds `x` to register `y`.
+    Add(Value, Value),
+    /// Extension, `mul x y` multiplies register `y` by `x`.
+    Multiply(Value, Value),
+    /// Extension, does nothing.
+    Nop,
+    /// Extension, reads the next input value into a register.
+    In(usize),
 }
 
-impl Program {
-    pub fn new(instructions: Vec<Instruction>) -> crate::prelude::Result<Self> {
-        for instr in &instructions {
-            match instr {
-                Instruction::Toggle(reg)
-                | Instruction::Increment(reg)
-                | Instruction::Decrement(reg)
-                | Instruction::Copy(Value::Register(reg), Value::Constant(_))
-                | Instruction::JumpIfNotZero(Value::Register(reg), Value::Constant(_))
-                | Instruction::Copy(Value::Constant(_), Value::Register(reg))
-                | Instruction::JumpIfNotZero(Value::Constant(_), Value::Register(reg))
-                    if *reg > REGISTERY_SIZE =>
+/// A loop recognized by the peephole optimizer, executed in a single step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fused {
+    /// `inc target / dec counter / jnz counter -2`, in either order and with
+    /// `dec target` for a negative sign: `target += sign * counter`.
+    Add {
+        target: usize,
+        sign: i64,
+        counter: usize,
+    },
+    /// `cpy source inner` followed by an add loop on `inner`, then
+    /// `dec outer / jnz outer -5`: `target += sign * source * outer`.
+    Multiply {
+        target: usize,
+        sign: i64,
+        source: Value,
+        inner: usize,
+        outer: usize,
+    },
+}
+
+impl Fused {
+    /// Largest number of instructions a fused operation spans.
+    const MAX_LEN: usize = 6;
+
+    fn len(&self) -> i64 {
+        match self {
+            Fused::Add { .. } => 3,
+            Fused::Multiply { .. } => 6,
+        }
+    }
+
+    /// Recognizes a fused operation at the start of `instructions`.
+    fn detect(instructions: &[Instruction]) -> Option<Fused> {
+        use Instruction::*;
+        let add = |instructions: &[Instruction]| match *instructions.get(..3)? {
+            [Increment(target), Decrement(counter), JumpIfNotZero(Value::Register(cond), Value::Constant(-2))]
+            | [Decrement(counter), Increment(target), JumpIfNotZero(Value::Register(cond), Value::Constant(-2))]
+                if target != counter && cond == counter =>
+            {
+                Some((target, 1, counter))
+            }
+            [Decrement(target), Decrement(counter), JumpIfNotZero(Value::Register(cond), Value::Constant(-2))]
+                if target != counter && cond == counter =>
+            {
+                Some((target, -1, counter))
+            }
+            _ => None,
+        };
+
+        if let [Copy(source, Value::Register(inner)), ..] = *instructions {
+            if let Some((target, sign, counter)) = add(&instructions[1..]) {
+                if let [Decrement(outer), JumpIfNotZero(Value::Register(cond), Value::Constant(-5))] =
+                    *instructions.get(4..6)?
                 {
-                    return Err(anyhow!("invalid register in instruction {:?}", instr));
+                    let distinct = counter == inner
+                        && cond == outer
+                        && outer != target
+                        && outer != inner
+                        && source != Value::Register(target)
+                        && source != Value::Register(inner)
+                        && source != Value::Register(outer);
+                    if distinct {
+                        return Some(Fused::Multiply {
+                            target,
+                            sign,
+                            source,
+                            inner,
+                            outer,
+                        });
+                    }
                 }
-                Instruction::Copy(Value::Register(a), Value::Register(b))
-                | Instruction::JumpIfNotZero(Value::Register(a), Value::Register(b))
-                    if *a > REGISTERY_SIZE || *b > REGISTERY_SIZE =>
-                {
-                    return Err(anyhow!("invalid register in instruction {:?}", instr));
+            }
+        }
+        add(instructions).map(|(target, sign, counter)| Fused::Add {
+            target,
+            sign,
+            counter,
+        })
+    }
+
+    /// Executes the operation, returns false if the loop counters are not
+    /// positive, in which case the loop does not behave like a multiplication
+    /// and has to be interpreted instead, or if the result overflows.
+    fn execute(&self, registers: &mut [i64]) -> bool {
+        match *self {
+            Fused::Add {
+                target,
+                sign,
+                counter,
+            } => {
+                if registers[counter] <= 0 {
+                    return false;
+                }
+                let sum = sign
+                    .checked_mul(registers[counter])
+                    .and_then(|product| registers[target].checked_add(product));
+                match sum {
+                    Some(sum) => registers[target] = sum,
+                    None => return false,
+                }
+                registers[counter] = 0;
+            }
+            Fused::Multiply {
+                target,
+                sign,
+                source,
+                inner,
+                outer,
+            } => {
+                let source = source.resolve(registers);
+                if source <= 0 || registers[outer] <= 0 {
+                    return false;
+                }
+                let sum = sign
+                    .checked_mul(source)
+                    .and_then(|product| product.checked_mul(registers[outer]))
+                    .and_then(|product| registers[target].checked_add(product));
+                match sum {
+                    Some(sum) => registers[target] = sum,
+                    None => return false,
                 }
-                _ => {}
+                registers[inner] = 0;
+                registers[outer] = 0;
             }
         }
+        true
+    }
+}
+
+impl Program {
+    /// Creates a program in the puzzle dialect.
+    pub fn new(instructions: Vec<Instruction>) -> crate::prelude::Result<Self> {
+        Program::with_dialect(instructions, Dialect::PUZZLE)
+    }
+
+    /// Creates a program, rejecting instructions the dialect does not have.
+    /// Errors refer to instruction `n` as line `n + 1`, which is where it is
+    /// in the puzzle input.
+    pub fn with_dialect(
+        instructions: Vec<Instruction>,
+        dialect: Dialect,
+    ) -> crate::prelude::Result<Self> {
+        if !(1..=26).contains(&dialect.registers) {
+            return Err(anyhow!(
+                "a program has 1 to 26 registers, not {}",
+                dialect.registers
+            ));
+        }
+        for (idx, instr) in instructions.iter().enumerate() {
+            dialect
+                .validate(instr)
+                .map_err(|err| anyhow!("line {}: {}", idx + 1, err))?;
+        }
 
-        Ok(Program {
+        let mut program = Program {
             instructions,
             instruction_ptr: 0,
-        })
+            fused: Some(Vec::new()),
+            compiled: None,
+            dialect,
+            input: VecDeque::new(),
+        };
+        program.optimize();
+        Ok(program)
+    }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Queues a value for an `in` instruction to read.
+    pub fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    /// Whether the program stopped at an `in` instruction, because all input
+    /// has been read.
+    pub fn is_waiting_for_input(&self) -> bool {
+        self.input.is_empty()
+            && matches!(
+                self.instructions.get(self.instruction_ptr as usize),
+                Some(Instruction::In(_)) if self.instruction_ptr >= 0
+            )
+    }
+
+    /// Disables the peephole optimizer, so that every instruction is executed
+    /// one at a time.
+    pub fn without_optimizations(mut self) -> Self {
+        self.fused = None;
+        self.compiled = None;
+        self
     }
 
-    pub fn run_one<F>(&mut self, registers: &mut Registers, mut transmit: F) -> bool
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// The fused operation starting at an instruction, if any.
+    pub fn fused(&self, idx: usize) -> Option<Fused> {
+        self.fused.as_ref()?.get(idx).copied().flatten()
+    }
+
+    fn optimize(&mut self) {
+        let instructions = &self.instructions;
+        if let Some(fused) = &mut self.fused {
+            *fused = (0..instructions.len())
+                .map(|idx| Fused::detect(&instructions[idx..]))
+                .collect();
+        }
+    }
+
+    // Re-detects the fused operations that could include a changed instruction
+    fn reoptimize_around(&mut self, changed: usize) {
+        let first = changed.saturating_sub(Fused::MAX_LEN - 1);
+        if let Some(fused) = &mut self.fused {
+            for (idx, fused) in fused.iter_mut().enumerate().take(changed + 1).skip(first) {
+                *fused = Fused::detect(&self.instructions[idx..]);
+            }
+        }
+        if let Some(mut compiled) = self.compiled.take() {
+            for (idx, op) in compiled
+                .iter_mut()
+                .enumerate()
+                .take(changed + 1)
+                .skip(first)
+            {
+                *op = self.compile_op(idx);
+            }
+            self.compiled = Some(compiled);
+        }
+    }
+
+    fn compile_op(&self, idx: usize) -> Op {
+        if let Some(fused) = self.fused(idx) {
+            return Op::Fused(fused);
+        }
+        let target = |offset: i64| idx as i64 + offset;
+        match self.instructions[idx] {
+            Instruction::Increment(reg) => Op::Increment(reg),
+            Instruction::Decrement(reg) => Op::Decrement(reg),
+            Instruction::Copy(Value::Constant(value), Value::Register(reg)) => {
+                Op::CopyConstant(value, reg)
+            }
+            Instruction::Copy(Value::Register(source), Value::Register(reg)) => {
+                Op::CopyRegister(source, reg)
+            }
+            Instruction::Copy(_, Value::Constant(_))
+            | Instruction::JumpIfNotZero(Value::Constant(0), _)
+            | Instruction::Nop => Op::Skip,
+            Instruction::JumpIfNotZero(Value::Constant(_), Value::Constant(offset)) => {
+                Op::Jump(target(offset))
+            }
+            Instruction::JumpIfNotZero(Value::Register(reg), Value::Constant(offset)) => {
+                Op::JumpIfNotZero(reg, target(offset))
+            }
+            Instruction::Out(value) => Op::Out(value),
+            _ => Op::Interpret,
+        }
+    }
+
+    fn compile(&mut self) {
+        if self.compiled.is_none() {
+            self.compiled = Some(
+                (0..self.instructions.len())
+                    .map(|idx| self.compile_op(idx))
+                    .collect(),
+            );
+        }
+    }
+
+    /// Runs the program until it halts or waits for input, which returns
+    /// false, or until `transmit` breaks, which returns true.
+    pub fn run<F>(&mut self, backend: Backend, registers: &mut [i64], transmit: F) -> bool
+    where
+        F: FnMut(i64) -> ControlFlow<()>,
+    {
+        let mut budget = u64::MAX;
+        matches!(
+            self.run_for(backend, registers, &mut budget, transmit),
+            Exit::Break
+        )
+    }
+
+    // Like `run`, but also stops once `budget` steps ran
+    fn run_for<F>(
+        &mut self,
+        backend: Backend,
+        registers: &mut [i64],
+        budget: &mut u64,
+        mut transmit: F,
+    ) -> Exit
+    where
+        F: FnMut(i64) -> ControlFlow<()>,
+    {
+        if backend == Backend::Compiled {
+            self.compile();
+        }
+        loop {
+            if backend == Backend::Compiled {
+                let ops = self.compiled.as_ref().unwrap();
+                match execute(
+                    ops,
+                    &mut self.instruction_ptr,
+                    registers,
+                    budget,
+                    &mut transmit,
+                ) {
+                    Exit::Interpret => {}
+                    exit => return exit,
+                }
+            }
+            if *budget == 0 {
+                return Exit::Budget;
+            }
+            // Instructions the bytecode does not cover, and those that modify
+            // it, are left to the interpreter, which recompiles modified code
+            let mut flow = ControlFlow::Continue(());
+            if !self.run_one(registers, |value| flow = transmit(value)) {
+                return Exit::Halted;
+            }
+            *budget -= 1;
+            if flow.is_break() {
+                return Exit::Break;
+            }
+        }
+    }
+
+    /// Runs the program for at most `max_steps` steps, stopping early once it
+    /// halts, waits for input or provably loops forever. Like
+    /// [`Profile::steps`], a fused operation counts as a single step.
+    ///
+    /// A program loops forever once its state repeats: the instruction
+    /// pointer, registers, instructions and pending input. The state is
+    /// compared every few thousand steps, so a loop is noticed some time
+    /// after it started.
+    pub fn run_bounded<F>(
+        &mut self,
+        backend: Backend,
+        registers: &mut [i64],
+        max_steps: u64,
+        mut transmit: F,
+    ) -> Outcome
     where
-        F: FnMut(i64) -> (),
+        F: FnMut(i64),
+    {
+        let mut transmit = |value| {
+            transmit(value);
+            ControlFlow::Continue(())
+        };
+        let mut steps = 0;
+        // Brent's cycle detection on the states at the end of each interval
+        let mut saved = self.state(registers);
+        let (mut power, mut length) = (1, 0);
+        loop {
+            if steps == max_steps {
+                return Outcome::StepLimit { steps };
+            }
+            let interval = (max_steps - steps).min(LOOP_CHECK_INTERVAL);
+            let mut budget = interval;
+            let exit = self.run_for(backend, registers, &mut budget, &mut transmit);
+            steps += interval - budget;
+            if let Exit::Halted = exit {
+                return if self.is_waiting_for_input() {
+                    Outcome::WaitingForInput { steps }
+                } else {
+                    Outcome::Halted { steps }
+                };
+            }
+
+            if self.is_in(&saved, registers) {
+                // The saved state is on the cycle, running until it comes
+                // around again gives the exact period
+                let mut period = 0;
+                loop {
+                    self.run_for(backend, registers, &mut 1, &mut transmit);
+                    period += 1;
+                    if self.is_in(&saved, registers) {
+                        break;
+                    }
+                }
+                return Outcome::Looping {
+                    steps: steps + period,
+                    period,
+                };
+            }
+            length += 1;
+            if length == power {
+                saved = self.state(registers);
+                power *= 2;
+                length = 0;
+            }
+        }
+    }
+
+    fn state(&self, registers: &[i64]) -> State {
+        State {
+            instruction_ptr: self.instruction_ptr,
+            registers: registers.to_vec(),
+            instructions: self.instructions.clone(),
+            input: self.input.clone(),
+        }
+    }
+
+    // Whether the program is in `state`, comparing the cheap parts first
+    fn is_in(&self, state: &State, registers: &[i64]) -> bool {
+        self.instruction_ptr == state.instruction_ptr
+            && registers == &state.registers[..]
+            && self.instructions == state.instructions
+            && self.input == state.input
+    }
+
+    /// Executes a single instruction, returns false once the program halted
+    /// or waits for input. `registers` needs at least as many registers as
+    /// the dialect has.
+    pub fn run_one<F>(&mut self, registers: &mut [i64], mut transmit: F) -> bool
+    where
+        F: FnMut(i64),
     {
         if self.instruction_ptr < 0 || self.instruction_ptr >= self.instructions.len() as i64 {
             return false;
         }
 
+        if let Some(fused) = self.fused(self.instruction_ptr as usize) {
+            if fused.execute(registers) {
+                self.instruction_ptr += fused.len();
+                return true;
+            }
+        }
+
         match &self.instructions[self.instruction_ptr as usize] {
             Instruction::Toggle(reg) => {
                 let new_ptr = registers[*reg] + self.instruction_ptr;
                 if new_ptr >= 0 && new_ptr < self.instructions.len() as i64 {
                     self.instructions[new_ptr as usize] =
                         self.instructions[new_ptr as usize].toggle();
+                    self.reoptimize_around(new_ptr as usize);
                 }
             }
             Instruction::Increment(reg) => registers[*reg] += 1,
@@ -86,34 +560,460 @@ impl Program {
                     return true;
                 }
             }
+            Instruction::Add(value, Value::Register(reg)) => {
+                registers[*reg] += value.resolve(registers)
+            }
+            Instruction::Multiply(value, Value::Register(reg)) => {
+                registers[*reg] *= value.resolve(registers)
+            }
+            Instruction::Add(_, Value::Constant(_))
+            | Instruction::Multiply(_, Value::Constant(_))
+            | Instruction::Nop => {}
+            Instruction::In(reg) => match self.input.pop_front() {
+                Some(value) => registers[*reg] = value,
+                None => return false,
+            },
         }
         self.instruction_ptr += 1;
 
         true
     }
 
-    pub fn run_to_end<F>(&mut self, registers: &mut Registers, mut transmit: F)
+    pub fn run_to_end<F>(&mut self, registers: &mut [i64], mut transmit: F)
+    where
+        F: FnMut(i64),
+    {
+        while self.run_one(registers, &mut transmit) {}
+    }
+
+    /// Executes a single instruction like [`Program::run_one`], recording it in
+    /// `profile` and writing the step, instruction index and resulting
+    /// registers to `trace`.
+    pub fn run_profiled<F>(
+        &mut self,
+        registers: &mut [i64],
+        profile: &mut Profile,
+        trace: Option<&mut dyn Write>,
+        transmit: F,
+    ) -> io::Result<bool>
     where
-        F: FnMut(i64) -> (),
+        F: FnMut(i64),
     {
-        while self.run_one(registers, |v| transmit(v)) {}
+        let ptr = self.instruction_ptr;
+        if !self.run_one(registers, transmit) {
+            return Ok(false);
+        }
+        let idx = ptr as usize;
+        if profile.counts.len() < self.instructions.len() {
+            profile.counts.resize(self.instructions.len(), 0);
+        }
+        profile.steps += 1;
+        profile.counts[idx] += 1;
+        let fused = self
+            .fused(idx)
+            .is_some_and(|fused| self.instruction_ptr == ptr + fused.len());
+        if fused {
+            profile.fused_steps += 1;
+        } else if self.instruction_ptr != ptr + 1 {
+            *profile
+                .jumps
+                .entry((idx, self.instruction_ptr))
+                .or_insert(0) += 1;
+        }
+        if let Some(trace) = trace {
+            write!(trace, "{} {}", profile.steps, idx)?;
+            for value in &registers[..self.dialect.registers] {
+                write!(trace, " {}", value)?;
+            }
+            writeln!(trace)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Long-run behavior of the `out` stream of a program, see
+/// [`Program::output_stream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputStream {
+    /// The program halted or waits for input after transmitting these values.
+    Finite(Vec<i64>),
+    /// After `prefix`, the program transmits `period` over and over forever.
+    Periodic { prefix: Vec<i64>, period: Vec<i64> },
+    /// The values transmitted before the output limit, or before the program
+    /// ran the step limit without transmitting, without a repetition.
+    Unknown(Vec<i64>),
+}
+
+impl OutputStream {
+    /// The value transmitted at `idx`, if it is known.
+    pub fn get(&self, idx: usize) -> Option<i64> {
+        match self {
+            OutputStream::Finite(output) | OutputStream::Unknown(output) => {
+                output.get(idx).copied()
+            }
+            OutputStream::Periodic { prefix, period } => {
+                Some(match idx.checked_sub(prefix.len()) {
+                    Some(idx) => period[idx % period.len()],
+                    None => prefix[idx],
+                })
+            }
+        }
+    }
+
+    /// Whether the stream is `pattern` repeated forever.
+    pub fn repeats(&self, pattern: &[i64]) -> bool {
+        match self {
+            OutputStream::Periodic { prefix, period } if !pattern.is_empty() => {
+                // Past the prefix both repeat every `period.len() *
+                // pattern.len()` values
+                let len = prefix.len() + period.len() * pattern.len();
+                (0..len).all(|idx| self.get(idx) == Some(pattern[idx % pattern.len()]))
+            }
+            _ => false,
+        }
     }
 }
 
+/// When [`Program::output_stream`] gives up on a stream that has not
+/// repeated yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Number of values transmitted.
+    pub outputs: usize,
+    /// Number of steps without transmitting a value.
+    pub steps: u64,
+}
+
+impl Program {
+    /// Runs the program until its `out` stream repeats, it halts or waits for
+    /// input, or it reaches one of the `limits`.
+    ///
+    /// The stream repeats once the program is in the same state after two
+    /// outputs, with the same instruction pointer, registers, instructions
+    /// and pending input.
+    pub fn output_stream(
+        &mut self,
+        backend: Backend,
+        registers: &mut [i64],
+        limits: OutputLimits,
+    ) -> OutputStream {
+        self.follow_output(backend, registers, limits, |_, _| true)
+            .unwrap()
+    }
+
+    // Like `output_stream`, but returns `None` as soon as `accept` rejects a
+    // value, given its index in the stream
+    fn follow_output<F>(
+        &mut self,
+        backend: Backend,
+        registers: &mut [i64],
+        limits: OutputLimits,
+        mut accept: F,
+    ) -> Option<OutputStream>
+    where
+        F: FnMut(usize, i64) -> bool,
+    {
+        let mut output = Vec::new();
+        let mut seen = HashMap::new();
+        loop {
+            if let Some(start) = seen.insert(self.state(registers), output.len()) {
+                let period = output.split_off(start);
+                return Some(OutputStream::Periodic {
+                    prefix: output,
+                    period,
+                });
+            }
+            if output.len() >= limits.outputs {
+                return Some(OutputStream::Unknown(output));
+            }
+            let mut accepted = true;
+            let mut budget = limits.steps;
+            let exit = self.run_for(backend, registers, &mut budget, |value| {
+                accepted = accept(output.len(), value);
+                output.push(value);
+                ControlFlow::Break(())
+            });
+            match exit {
+                Exit::Halted => return Some(OutputStream::Finite(output)),
+                Exit::Budget => return Some(OutputStream::Unknown(output)),
+                Exit::Break | Exit::Interpret => {}
+            }
+            if !accepted {
+                return None;
+            }
+        }
+    }
+
+    /// Finds the first value in `candidates` that makes the program transmit
+    /// `pattern` repeated forever, when stored in `register` on top of
+    /// `registers`. Each candidate runs from the current state of the
+    /// program, and is rejected at the first value that does not match, or
+    /// when it reaches one of the `limits` before its stream repeats.
+    pub fn find_input(
+        &self,
+        backend: Backend,
+        registers: &[i64],
+        register: usize,
+        candidates: Range<i64>,
+        pattern: &[i64],
+        limits: OutputLimits,
+    ) -> anyhow::Result<i64> {
+        if pattern.is_empty() {
+            return Err(anyhow!("the output pattern is empty"));
+        }
+        let name = Value::Register(register);
+        if register >= self.dialect.registers {
+            return Err(anyhow!(
+                "register {} is out of range, the program has {} registers",
+                name,
+                self.dialect.registers
+            ));
+        }
+        let mut program = self.clone();
+        if backend == Backend::Compiled {
+            program.compile();
+        }
+        for candidate in candidates.clone() {
+            crate::cancel::check()?;
+            let mut attempt = program.clone();
+            let mut registers = registers.to_vec();
+            registers[register] = candidate;
+            let stream = attempt.follow_output(backend, &mut registers, limits, |idx, value| {
+                value == pattern[idx % pattern.len()]
+            });
+            if stream.is_some_and(|stream| stream.repeats(pattern)) {
+                return Ok(candidate);
+            }
+        }
+        Err(anyhow!(
+            "no value of {} in {:?} makes the program transmit {} repeatedly",
+            name,
+            candidates,
+            pattern
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        ))
+    }
+}
+
+/// How [`Program::run_bounded`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The instruction pointer left the program.
+    Halted { steps: u64 },
+    /// An `in` instruction found all input read.
+    WaitingForInput { steps: u64 },
+    /// The program neither halted nor repeated its state within the limit.
+    StepLimit { steps: u64 },
+    /// The state repeats every `period` steps, which was noticed after
+    /// `steps` steps.
+    Looping { steps: u64, period: u64 },
+}
+
+impl Outcome {
+    /// The number of steps until the program halted, or an error describing
+    /// why it did not.
+    pub fn halted(self) -> anyhow::Result<u64> {
+        match self {
+            Outcome::Halted { steps } => Ok(steps),
+            outcome => Err(anyhow!("{}", outcome)),
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Outcome::Halted { steps } => write!(f, "the program halted after {} steps", steps),
+            Outcome::WaitingForInput { steps } => write!(
+                f,
+                "the program waits for input after {} steps, all input has been read",
+                steps
+            ),
+            Outcome::StepLimit { steps } => {
+                write!(f, "the program did not halt within {} steps", steps)
+            }
+            Outcome::Looping { steps, period } => write!(
+                f,
+                "the program loops forever, its state repeats every {} steps (noticed after {} steps)",
+                period, steps
+            ),
+        }
+    }
+}
+
+// Everything that determines how a program continues
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct State {
+    instruction_ptr: i64,
+    registers: Vec<i64>,
+    instructions: Vec<Instruction>,
+    input: VecDeque<i64>,
+}
+
+/// How [`Program::run`] executes a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Decodes each instruction as it runs, see [`Program::run_one`].
+    Interpreter,
+    /// Runs pre-decoded bytecode with absolute jump targets, falling back to
+    /// the interpreter for `tgl` and the instructions it cannot decode ahead
+    /// of time.
+    Compiled,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "interpreter" => Ok(Backend::Interpreter),
+            "compiled" => Ok(Backend::Compiled),
+            _ => Err(anyhow!("expected interpreter or compiled, got {:?}", s)),
+        }
+    }
+}
+
+// A pre-decoded instruction, jump targets are absolute instruction indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Increment(usize),
+    Decrement(usize),
+    CopyConstant(i64, usize),
+    CopyRegister(usize, usize),
+    Jump(i64),
+    JumpIfNotZero(usize, i64),
+    Out(Value),
+    Fused(Fused),
+    // An instruction without effect
+    Skip,
+    // Executed by the interpreter
+    Interpret,
+}
+
+enum Exit {
+    Halted,
+    Break,
+    Interpret,
+    Budget,
+}
+
+// Runs ops until one needs the interpreter, or `budget` steps ran
+fn execute<F>(
+    ops: &[Op],
+    ptr: &mut i64,
+    registers: &mut [i64],
+    budget: &mut u64,
+    transmit: &mut F,
+) -> Exit
+where
+    F: FnMut(i64) -> ControlFlow<()>,
+{
+    let mut idx = *ptr;
+    let mut remaining = *budget;
+    let exit = loop {
+        let op = match ops.get(idx as usize) {
+            Some(&op) if idx >= 0 => op,
+            _ => break Exit::Halted,
+        };
+        if remaining == 0 {
+            break Exit::Budget;
+        }
+        match op {
+            Op::Increment(reg) => registers[reg] += 1,
+            Op::Decrement(reg) => registers[reg] -= 1,
+            Op::CopyConstant(value, reg) => registers[reg] = value,
+            Op::CopyRegister(source, reg) => registers[reg] = registers[source],
+            Op::Jump(target) => {
+                remaining -= 1;
+                idx = target;
+                continue;
+            }
+            Op::JumpIfNotZero(reg, target) => {
+                if registers[reg] != 0 {
+                    remaining -= 1;
+                    idx = target;
+                    continue;
+                }
+            }
+            Op::Out(value) => {
+                if transmit(value.resolve(registers)).is_break() {
+                    remaining -= 1;
+                    idx += 1;
+                    break Exit::Break;
+                }
+            }
+            Op::Fused(fused) => {
+                if !fused.execute(registers) {
+                    break Exit::Interpret;
+                }
+                remaining -= 1;
+                idx += fused.len();
+                continue;
+            }
+            Op::Skip => {}
+            Op::Interpret => break Exit::Interpret,
+        }
+        remaining -= 1;
+        idx += 1;
+    };
+    *ptr = idx;
+    *budget = remaining;
+    exit
+}
+
 impl Instruction {
+    /// Follows the rules of `tgl`, which turn extension instructions with one
+    /// argument into `inc`, and those with two into `jnz`.
     pub fn toggle(self) -> Instruction {
         match self {
-            Instruction::Toggle(x) | Instruction::Decrement(x) => Instruction::Increment(x),
+            Instruction::Toggle(x) | Instruction::Decrement(x) | Instruction::In(x) => {
+                Instruction::Increment(x)
+            }
             Instruction::Increment(x) => Instruction::Decrement(x),
-            Instruction::Copy(x, y) => Instruction::JumpIfNotZero(x, y),
+            Instruction::Copy(x, y) | Instruction::Add(x, y) | Instruction::Multiply(x, y) => {
+                Instruction::JumpIfNotZero(x, y)
+            }
             Instruction::JumpIfNotZero(x, y) => Instruction::Copy(x, y),
-            x @ Instruction::Out(_) => x,
+            x @ (Instruction::Out(_) | Instruction::Nop) => x,
+        }
+    }
+
+    pub fn is_extension(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Add(..)
+                | Instruction::Multiply(..)
+                | Instruction::Nop
+                | Instruction::In(_)
+        )
+    }
+
+    /// The registers the instruction reads or writes.
+    pub fn registers(&self) -> [Option<usize>; 2] {
+        let register = |value: &Value| match *value {
+            Value::Register(register) => Some(register),
+            Value::Constant(_) => None,
+        };
+        match self {
+            Instruction::Toggle(x)
+            | Instruction::Increment(x)
+            | Instruction::Decrement(x)
+            | Instruction::In(x) => [Some(*x), None],
+            Instruction::Out(x) => [register(x), None],
+            Instruction::Copy(x, y)
+            | Instruction::JumpIfNotZero(x, y)
+            | Instruction::Add(x, y)
+            | Instruction::Multiply(x, y) => [register(x), register(y)],
+            Instruction::Nop => [None, None],
         }
     }
 }
 
 impl Value {
-    pub fn resolve(&self, registers: &Registers) -> i64 {
+    pub fn resolve(&self, registers: &[i64]) -> i64 {
         match *self {
             Value::Constant(value) => value,
             Value::Register(index) => registers[index],
@@ -121,28 +1021,51 @@ impl Value {
     }
 }
 
+/// Writes one instruction per line, in the syntax of the puzzle input. The
+/// alternate form (`{:#}`) marks the instruction pointer with a `>`.
 impl Display for Program {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         use fmt::Write;
         for (idx, instr) in self.instructions.iter().enumerate() {
-            f.write_char(if self.instruction_ptr == idx as i64 {
-                '>'
-            } else {
-                ' '
-            })?;
-            match instr {
-                Instruction::Toggle(v) => write!(f, "tgl {}\n", v),
-                Instruction::Increment(v) => write!(f, "inc {}\n", v),
-                Instruction::Decrement(v) => write!(f, "dec {}\n", v),
-                Instruction::Out(v) => write!(f, "out {}\n", v),
-                Instruction::Copy(a, b) => write!(f, "cpy {} {}\n", a, b),
-                Instruction::JumpIfNotZero(a, b) => write!(f, "jnz {} {}\n", a, b),
-            }?;
+            if f.alternate() {
+                f.write_char(if self.instruction_ptr == idx as i64 {
+                    '>'
+                } else {
+                    ' '
+                })?;
+            }
+            writeln!(f, "{}", instr)?;
         }
         Ok(())
     }
 }
 
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Instruction::Toggle(r) => write!(f, "tgl {}", Value::Register(*r)),
+            Instruction::Increment(r) => write!(f, "inc {}", Value::Register(*r)),
+            Instruction::Decrement(r) => write!(f, "dec {}", Value::Register(*r)),
+            Instruction::Out(v) => write!(f, "out {}", v),
+            Instruction::Copy(a, b) => write!(f, "cpy {} {}", a, b),
+            Instruction::JumpIfNotZero(a, b) => write!(f, "jnz {} {}", a, b),
+            Instruction::Add(a, b) => write!(f, "add {} {}", a, b),
+            Instruction::Multiply(a, b) => write!(f, "mul {} {}", a, b),
+            Instruction::Nop => f.write_str("nop"),
+            Instruction::In(r) => write!(f, "in {}", Value::Register(*r)),
+        }
+    }
+}
+
+/// Writes instructions in the syntax of the puzzle input, which
+/// [`parse_assembunny`] parses back into the same instructions.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(|instr| format!("{}\n", instr))
+        .collect()
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         use fmt::Write;
@@ -153,10 +1076,132 @@ impl Display for Value {
     }
 }
 
+/// Execution counts collected by [`Program::run_profiled`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub steps: u64,
+    /// Steps that executed a fused operation, each counts as a single step.
+    pub fused_steps: u64,
+    /// Number of executions of each instruction, a fused operation counts
+    /// towards the instruction it starts at.
+    pub counts: Vec<u64>,
+    /// Number of times each jump was taken, by source and target index.
+    pub jumps: BTreeMap<(usize, i64), u64>,
+}
+
+/// Instructions repeated by a backward jump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Loop {
+    pub start: usize,
+    /// The jump back to the start, the last instruction of the body.
+    pub end: usize,
+    pub iterations: u64,
+}
+
+impl Profile {
+    /// Loop bodies found from the jumps taken, outer loops first.
+    pub fn loops(&self) -> Vec<Loop> {
+        let mut loops: Vec<Loop> = self
+            .jumps
+            .iter()
+            .filter(|&(&(from, to), _)| to >= 0 && to as usize <= from)
+            .map(|(&(from, to), &iterations)| Loop {
+                start: to as usize,
+                end: from,
+                iterations,
+            })
+            .collect();
+        loops.sort_by_key(|l| (l.start, std::cmp::Reverse(l.end)));
+        loops
+    }
+
+    /// The program listing annotated with execution counts and loop bodies.
+    pub fn annotate<'a>(&'a self, program: &'a Program) -> Annotated<'a> {
+        Annotated {
+            profile: self,
+            program,
+        }
+    }
+}
+
+/// Display of [`Profile::annotate`].
+pub struct Annotated<'a> {
+    profile: &'a Profile,
+    program: &'a Program,
+}
+
+impl Display for Annotated<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let loops = self.profile.loops();
+        // Assign each loop the leftmost column not used by an overlapping loop
+        let mut column_ends: Vec<usize> = Vec::new();
+        let columns: Vec<usize> = loops
+            .iter()
+            .map(
+                |l| match column_ends.iter().position(|&end| end < l.start) {
+                    Some(column) => {
+                        column_ends[column] = l.end;
+                        column
+                    }
+                    None => {
+                        column_ends.push(l.end);
+                        column_ends.len() - 1
+                    }
+                },
+            )
+            .collect();
+
+        let steps = self.profile.steps.max(1) as f64;
+        let listing = format!("{:#}", self.program);
+        for (idx, line) in listing.lines().enumerate() {
+            let count = self.profile.counts.get(idx).copied().unwrap_or(0);
+            write!(
+                f,
+                "{:>3} {:>12} {:>5.1}% ",
+                idx,
+                count,
+                count as f64 * 100.0 / steps
+            )?;
+            let mut gutter = vec![' '; column_ends.len()];
+            for (l, &column) in loops.iter().zip(&columns) {
+                gutter[column] = match idx {
+                    idx if idx == l.start && idx == l.end => '<',
+                    idx if idx == l.start => '/',
+                    idx if idx == l.end => '\\',
+                    idx if idx > l.start && idx < l.end => '|',
+                    _ => continue,
+                };
+            }
+            write!(f, "{} {}", gutter.into_iter().collect::<String>(), line)?;
+            match self.program.fused(idx) {
+                Some(Fused::Add { .. }) => writeln!(f, "  ; fused add")?,
+                Some(Fused::Multiply { .. }) => writeln!(f, "  ; fused multiply")?,
+                None => writeln!(f)?,
+            }
+        }
+
+        write!(f, "{} steps", self.profile.steps)?;
+        if self.profile.fused_steps > 0 {
+            write!(f, ", {} of which fused", self.profile.fused_steps)?;
+        }
+        for l in &loops {
+            write!(
+                f,
+                "\nloop {}..={}: {} iterations",
+                l.start, l.end, l.iterations
+            )?;
+        }
+        for (&(from, to), count) in &self.profile.jumps {
+            write!(f, "\njump {} -> {}: {} times", from, to, count)?;
+        }
+        Ok(())
+    }
+}
+
 pub fn parse_register(s: &str) -> nom::IResult<&str, usize> {
     use crate::parsers::*;
-    map_res(anychar, |c| {
-        if c >= 'a' && c <= 'z' {
+    map_res(anychar, |c: char| {
+        if c.is_ascii_lowercase() {
             Ok((c as u8 - b'a') as usize)
         } else {
             Err(())
@@ -171,7 +1216,7 @@ pub fn parse_value(s: &str) -> nom::IResult<&str, Value> {
     ))(s)
 }
 
-pub fn parse_assembunny(s: &str) -> nom::IResult<&str, Vec<Instruction>> {
+pub fn parse_instruction(s: &str) -> nom::IResult<&str, Instruction> {
     use crate::parsers::*;
     fn instr_1op<I>(name: &'static str, instr: I) -> impl Fn(&str) -> IResult<&str, Instruction>
     where
@@ -199,14 +1244,433 @@ pub fn parse_assembunny(s: &str) -> nom::IResult<&str, Vec<Instruction>> {
         }
     }
 
-    let instruction = alt((
+    alt((
         instr_1op("tgl", Instruction::Toggle),
         instr_1op("inc", Instruction::Increment),
         instr_1op("dec", Instruction::Decrement),
         map(preceded(tag("out "), parse_value), Instruction::Out),
         instr_2op("cpy", Instruction::Copy),
         instr_2op("jnz", Instruction::JumpIfNotZero),
-    ));
+        instr_2op("add", Instruction::Add),
+        instr_2op("mul", Instruction::Multiply),
+        map(tag("nop"), |_| Instruction::Nop),
+        instr_1op("in", Instruction::In),
+    ))(s)
+}
+
+pub fn parse_assembunny(s: &str) -> nom::IResult<&str, Vec<Instruction>> {
+    use crate::parsers::*;
+    separated_list1(line_ending, parse_instruction)(s)
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    // Single lowercase letters are registers
+    s.len() > 1
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses assembunny source that may contain comments and labels, in
+/// addition to the syntax of the puzzle input.
+///
+/// Comments start with `#` or `;` and run until the end of the line. A line
+/// can start with a label (`loop: dec b`, or `loop:` on a line of its own to
+/// label the next instruction), and the target of a `jnz` may name a label
+/// instead of an offset (`jnz b loop`), which is resolved to the relative
+/// offset the puzzle input would use. Instructions the dialect does not have
+/// are rejected.
+pub fn assemble(source: &str, dialect: Dialect) -> anyhow::Result<Vec<Instruction>> {
+    use std::collections::HashMap;
+
+    // Collect the labels and the text of every instruction
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+    for (line_nr, line) in source.lines().enumerate() {
+        let line_nr = line_nr + 1;
+        let mut line = line.split(['#', ';']).next().unwrap().trim();
+        if let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(anyhow!("line {}: invalid label {:?}", line_nr, label));
+            }
+            if labels.insert(label, lines.len()).is_some() {
+                return Err(anyhow!("line {}: duplicate label {}", line_nr, label));
+            }
+            line = rest.trim();
+        }
+        if !line.is_empty() {
+            lines.push((line_nr, line));
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, &(line_nr, line))| {
+            let mut tokens: Vec<String> = line.split_whitespace().map(str::to_owned).collect();
+            if let [op, _, target] = tokens.as_mut_slice() {
+                if op == "jnz" && is_label(target) {
+                    match labels.get(target.as_str()) {
+                        Some(&target_idx) => *target = (target_idx as i64 - idx as i64).to_string(),
+                        None => return Err(anyhow!("line {}: unknown label {}", line_nr, target)),
+                    }
+                }
+            }
+            let instruction =
+                crate::parse_error::parse_all(&tokens.join(" "), parse_instruction)
+                    .map_err(|_| anyhow!("line {}: invalid instruction {:?}", line_nr, line))?;
+            dialect
+                .validate(&instruction)
+                .map_err(|err| anyhow!("line {}: {}", line_nr, err))?;
+            Ok(instruction)
+        })
+        .collect()
+}
+
+#[test]
+fn assembunny() -> crate::prelude::Result<()> {
+    fn run(source: &str, a: i64, optimize: bool) -> crate::prelude::Result<(Registers, usize)> {
+        let instructions = crate::parse_error::parse_all(source, parse_assembunny)?;
+        let mut program = Program::new(instructions)?;
+        if !optimize {
+            program = program.without_optimizations();
+        }
+        let mut registers = [a, 0, 0, 0];
+        let mut steps = 0;
+        while program.run_one(&mut registers, |_| {}) {
+            steps += 1;
+        }
+        Ok((registers, steps))
+    }
+
+    let add = "cpy 7 b\ninc a\ndec b\njnz b -2\ndec c";
+    let program = Program::new(parse_assembunny(add).unwrap().1)?;
+    assert_eq!(
+        program.fused(1),
+        Some(Fused::Add {
+            target: 0,
+            sign: 1,
+            counter: 1
+        })
+    );
+    assert_eq!(run(add, 3, true)?, ([10, 0, -1, 0], 3));
+    assert_eq!(run(add, 3, false)?.0, [10, 0, -1, 0]);
+
+    // Multiplies b by d, then toggles the `jnz d -5` into a `cpy` so that the
+    // second pass only runs the inner loop once, and toggles it back
+    let multiply = "\
+cpy 4 b
+cpy 3 d
+cpy b c
+inc a
+dec c
+jnz c -2
+dec d
+jnz d -5
+cpy -2 c
+tgl c
+jnz d 2
+jnz 1 -10";
+    let program = Program::new(parse_assembunny(multiply).unwrap().1)?;
+    assert!(matches!(program.fused(2), Some(Fused::Multiply { .. })));
+    assert_eq!(
+        program.fused(3),
+        Some(Fused::Add {
+            target: 0,
+            sign: 1,
+            counter: 2
+        })
+    );
+    let (optimized, steps) = run(multiply, 0, true)?;
+    let (interpreted, interpreted_steps) = run(multiply, 0, false)?;
+    assert_eq!(optimized, interpreted);
+    assert_eq!(optimized, [16, 4, -2, 2]);
+    assert!(steps < interpreted_steps);
+
+    // Operations that would overflow leave the registers alone, so that the
+    // loop is interpreted instead
+    let fused = Fused::Add {
+        target: 0,
+        sign: 1,
+        counter: 1,
+    };
+    let mut registers = [i64::MAX - 2, 3, 0, 0];
+    assert!(!fused.execute(&mut registers));
+    assert_eq!(registers, [i64::MAX - 2, 3, 0, 0]);
+    registers[1] = 2;
+    assert!(fused.execute(&mut registers));
+    assert_eq!(registers, [i64::MAX, 0, 0, 0]);
+    // a += b * d, where the product overflows but every inner loop does not
+    let large = "cpy b c\ninc a\ndec c\njnz c -2\ndec d\njnz d -5";
+    for &backend in &[Backend::Interpreter, Backend::Compiled] {
+        let mut program = Program::new(parse_assembunny(large).unwrap().1)?;
+        assert!(matches!(program.fused(0), Some(Fused::Multiply { .. })));
+        let mut registers = [0, 1 << 40, 0, 1 << 40];
+        let outcome = program.run_bounded(backend, &mut registers, 100, |_| {});
+        assert!(matches!(outcome, Outcome::StepLimit { .. }));
+        assert!(registers[3] < 1 << 40);
+        assert_eq!(registers[0], (1 << 40) * ((1 << 40) - registers[3]));
+    }
+
+    // Profiling counts every instruction literally, or the fused operation once
+    for &(optimize, counts, fused_steps) in &[
+        (false, &[1, 7, 7, 7, 1][..], 0),
+        (true, &[1, 1, 0, 0, 1][..], 1),
+    ] {
+        let mut program = Program::new(parse_assembunny(add).unwrap().1)?;
+        if !optimize {
+            program = program.without_optimizations();
+        }
+        let (mut registers, mut profile, mut trace) =
+            ([3, 0, 0, 0], Profile::default(), Vec::new());
+        while program.run_profiled(&mut registers, &mut profile, Some(&mut trace), |_| {})? {}
+        assert_eq!(profile.counts, counts);
+        assert_eq!(profile.fused_steps, fused_steps);
+        assert_eq!(
+            String::from_utf8(trace)?.lines().last(),
+            Some(&*format!("{} 4 10 0 -1 0", profile.steps))
+        );
+        if !optimize {
+            assert_eq!(
+                profile.loops(),
+                vec![Loop {
+                    start: 1,
+                    end: 3,
+                    iterations: 6
+                }]
+            );
+            assert_eq!(
+                profile.annotate(&program).to_string(),
+                "  0            1   4.3%    cpy 7 b
+  1            7  30.4% /  inc a
+  2            7  30.4% |  dec b
+  3            7  30.4% \\  jnz b -2
+  4            1   4.3%    dec c
+23 steps
+loop 1..=3: 6 iterations
+jump 3 -> 1: 6 times"
+            );
+        }
+    }
+
+    // Disassembled programs parse back into the same instructions, and
+    // labels resolve to relative offsets
+    let program = Program::new(parse_assembunny(multiply).unwrap().1)?;
+    assert_eq!(program.to_string(), format!("{}\n", multiply));
+    assert_eq!(
+        crate::parse_error::parse_all(&disassemble(program.instructions()), parse_assembunny)?,
+        program.instructions()
+    );
+    let labeled = "\
+cpy 4 b ; outer loop count
+cpy 3 d
+outer:
+  cpy b c
+inner: inc a
+  dec c
+  jnz c inner
+  dec d
+  jnz d outer
+# toggle the jnz d outer
+cpy -2 c
+tgl c
+jnz d end
+jnz 1 -10
+end:";
+    assert_eq!(assemble(labeled, Dialect::PUZZLE)?, program.instructions());
+    assert_eq!(assemble(multiply, Dialect::PUZZLE)?, program.instructions());
+    for (source, err) in &[
+        ("jnz a loop", "line 1: unknown label loop"),
+        ("x: inc a", "line 1: invalid label \"x\""),
+        ("l1: inc a\nl1: inc b", "line 2: duplicate label l1"),
+        ("inc 2", "line 1: invalid instruction \"inc 2\""),
+    ] {
+        assert_eq!(
+            assemble(source, Dialect::PUZZLE).unwrap_err().to_string(),
+            *err
+        );
+    }
+
+    // Extension instructions and registers are only available in dialects
+    // that have them, errors point at the offending line
+    let extended = "\
+in a
+cpy 3 e
+mul e a
+add -1 a
+nop
+inc b
+tgl b
+in b";
+    let err = |result: crate::prelude::Result<Program>| result.unwrap_err().to_string();
+    let instructions = parse_assembunny(extended).unwrap().1;
+    assert_eq!(
+        err(Program::new(instructions.clone())),
+        "line 1: in a is an extension instruction, which the puzzle dialect does not allow"
+    );
+    assert_eq!(
+        err(Program::with_dialect(
+            instructions.clone(),
+            Dialect::EXTENDED
+        )),
+        "line 2: register e in cpy 3 e is out of range, the program has 4 registers"
+    );
+    assert_eq!(
+        assemble(&format!("# extended\n{}", extended), Dialect::PUZZLE)
+            .unwrap_err()
+            .to_string(),
+        "line 2: in a is an extension instruction, which the puzzle dialect does not allow"
+    );
+    assert_eq!(
+        err(Program::new(vec![Instruction::Increment(4)])),
+        "line 1: register e in inc e is out of range, the program has 4 registers"
+    );
+    assert!(Program::with_dialect(Vec::new(), Dialect::PUZZLE.with_registers(27)).is_err());
+
+    let dialect = Dialect::EXTENDED.with_registers(5);
+    let mut program = Program::with_dialect(instructions, dialect)?;
+    assert_eq!(
+        disassemble(program.instructions()),
+        format!("{}\n", extended)
+    );
+    let mut registers = vec![0; 5];
+    assert!(!program.run_one(&mut registers, |_| {}));
+    assert!(program.is_waiting_for_input());
+    program.push_input(4);
+    program.run_to_end(&mut registers, |_| {});
+    // The tgl turns the last in into an inc
+    assert_eq!(registers, [11, 2, 0, 0, 3]);
+    assert!(!program.is_waiting_for_input());
+
+    // Both backends produce the same registers, output and toggled code
+    let signal = "cpy 3 b\nout a\ninc a\ndec b\njnz b -3\ncpy 0 c\njnz c c";
+    for source in &[add, multiply, labeled, signal] {
+        let mut results = Vec::new();
+        for &backend in &[Backend::Interpreter, Backend::Compiled] {
+            let mut program = Program::new(assemble(source, Dialect::PUZZLE)?)?;
+            let mut registers = [1, 0, 0, 0];
+            let mut output = Vec::new();
+            // Stops at the second output to resume from there
+            while program.run(backend, &mut registers, |value| {
+                output.push(value);
+                if output.len() == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }) {}
+            results.push((registers, output, program.instructions().to_vec()));
+        }
+        assert_eq!(results[0], results[1]);
+    }
+
+    // Output streams repeat once the state after an output does
+    let alternate = "\
+loop: out a
+  jnz a one
+  inc a
+  jnz 1 loop
+one:
+  dec a
+  jnz 1 loop";
+    const LIMITS: OutputLimits = OutputLimits {
+        outputs: 8,
+        steps: 1000,
+    };
+    let stream = |source: &str, a: i64| -> crate::prelude::Result<OutputStream> {
+        let mut program = Program::new(assemble(source, Dialect::PUZZLE)?)?;
+        Ok(program.output_stream(Backend::Compiled, &mut [a, 0, 0, 0], LIMITS))
+    };
+    let prefixed = stream(&format!("out 7\n{}", alternate), 0)?;
+    assert_eq!(
+        prefixed,
+        OutputStream::Periodic {
+            prefix: vec![7, 0],
+            period: vec![1, 0]
+        }
+    );
+    assert_eq!(prefixed.get(5), Some(0));
+    assert!(!prefixed.repeats(&[0, 1]));
+    assert!(stream(alternate, 0)?.repeats(&[0, 1]));
+    assert!(stream(alternate, 0)?.repeats(&[0, 1, 0, 1]));
+    assert_eq!(stream("out 1\nout 2", 0)?, OutputStream::Finite(vec![1, 2]));
+    assert_eq!(
+        stream("out a\ninc a\njnz 1 -2", 3)?,
+        OutputStream::Unknown((3..11).collect())
+    );
+    // Loops without output end at the step limit
+    assert_eq!(stream("jnz 1 0", 0)?, OutputStream::Unknown(vec![]));
+    assert_eq!(
+        stream("out 1\ninc a\njnz 1 -1", 0)?,
+        OutputStream::Unknown(vec![1])
+    );
+
+    // Negative candidates count down forever, and are rejected at their
+    // first output
+    let program = Program::new(assemble(alternate, Dialect::PUZZLE)?)?;
+    for &backend in &[Backend::Interpreter, Backend::Compiled] {
+        let find =
+            |pattern: &[i64]| program.find_input(backend, &[0; 4], 0, -3..10, pattern, LIMITS);
+        assert_eq!(find(&[0, 1])?, 0);
+        assert_eq!(find(&[1, 0])?, 1);
+        assert_eq!(
+            find(&[2, 1]).unwrap_err().to_string(),
+            "no value of a in -3..10 makes the program transmit 2,1 repeatedly"
+        );
+        // Candidates that fall silent forever are rejected too
+        let silent = Program::new(assemble(
+            &format!("jnz a 2\njnz 1 0\n{}", alternate),
+            Dialect::PUZZLE,
+        )?)?;
+        assert_eq!(
+            silent.find_input(backend, &[0; 4], 0, 0..10, &[1, 0], LIMITS)?,
+            1
+        );
+    }
+
+    // Bounded runs halt, run out of steps or notice that the state repeats
+    for &backend in &[Backend::Interpreter, Backend::Compiled] {
+        let outcome = |source: &str, max_steps: u64| -> crate::prelude::Result<Outcome> {
+            let mut program = Program::new(assemble(source, Dialect::PUZZLE)?)?;
+            Ok(program.run_bounded(backend, &mut [3, 0, 0, 0], max_steps, |_| {}))
+        };
+        assert_eq!(outcome(add, 100)?, Outcome::Halted { steps: 3 });
+        assert_eq!(outcome(add, 100)?.halted()?, 3);
+        assert_eq!(outcome(add, 2)?, Outcome::StepLimit { steps: 2 });
+        assert_eq!(
+            outcome("inc a\njnz 1 -1", 100_000)?,
+            Outcome::StepLimit { steps: 100_000 }
+        );
+        // The loop starts after a countdown, and takes 3 steps
+        let looping = "cpy 5000 b\ndec b\njnz b -1\ninc a\ndec a\njnz 1 -2";
+        match outcome(looping, u64::MAX)? {
+            Outcome::Looping { period, .. } => assert_eq!(period, 3),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+        assert_eq!(
+            outcome("jnz 1 0", 10_000)?
+                .halted()
+                .unwrap_err()
+                .to_string(),
+            "the program loops forever, its state repeats every 1 steps (noticed after 4097 steps)"
+        );
+    }
+    let mut program = Program::with_dialect(vec![Instruction::In(0)], Dialect::EXTENDED)?;
+    assert_eq!(
+        program.run_bounded(Backend::Compiled, &mut [0; 4], 10, |_| {}),
+        Outcome::WaitingForInput { steps: 0 }
+    );
+
+    // Subtraction, and jumping into the middle of a loop
+    for source in &[
+        "cpy 3 b\ndec a\ndec b\njnz b -2",
+        "cpy 2 b\njnz 1 3\ninc a\ndec b\njnz b -2",
+    ] {
+        assert_eq!(run(source, 5, true)?.0, run(source, 5, false)?.0);
+    }
 
-    separated_list1(line_ending, instruction)(s)
+    Ok(())
 }
@@ -1,19 +1,53 @@
 #![allow(dead_code)]
 
+use anyhow::{anyhow, Result};
 use num::traits::Zero;
 use std::cmp::{Ord, Ordering, PartialEq, PartialOrd};
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::ops::Add;
+use std::time::{Duration, Instant};
 
-pub trait Node = Clone + Eq + Hash;
-pub trait Cost = Clone + Ord + Add + Zero;
+pub trait Node: Clone + Eq + Hash {}
+impl<T: Clone + Eq + Hash> Node for T {}
+pub trait Cost: Clone + Ord + Add + Zero {}
+impl<T: Clone + Ord + Add + Zero> Cost for T {}
 
+/// Nodes from the start to the end of a path, each with the length of the
+/// path up to it.
+pub type Path<N, C> = Vec<(N, C)>;
+
+/// Reusable state for path finding, each search clears it before it starts,
+/// so the allocations are shared between searches.
 #[derive(Debug, Clone)]
 pub struct AStar<N: Node, C: Cost> {
     meta: HashMap<N, Meta<N, C>>,
     open: BinaryHeap<Open<N, C>>,
+    queue: VecDeque<N>,
     path: Vec<(N, C)>,
+    distances: HashMap<N, C>,
+    stats: Stats,
+    max_nodes: Option<usize>,
+}
+
+/// Work done by the last search of an [`AStar`], to compare heuristics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Nodes whose neighbors were visited.
+    pub expanded: usize,
+    /// Nodes added to the open set, including the start and nodes that were
+    /// added again because a shorter path to them was found.
+    pub pushed: usize,
+    /// Times a shorter path was found to a node that was still open.
+    pub reopened: usize,
+    /// Largest number of entries in the open set at once.
+    pub peak_open: usize,
+    pub heuristic_calls: usize,
+    /// Times a shorter path was found to a node that was already closed,
+    /// which only happens if the heuristic is inconsistent. The shorter path
+    /// is ignored, so the result may not be the shortest path.
+    pub inconsistencies: usize,
+    pub elapsed: Duration,
 }
 
 #[derive(Debug, Clone)]
@@ -51,20 +85,93 @@ impl<N: Node, C: Cost> Ord for Open<N, C> {
     }
 }
 
+impl<N: Node, C: Cost> Default for AStar<N, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<N: Node, C: Cost> AStar<N, C> {
     pub fn new() -> Self {
         AStar {
             meta: HashMap::new(),
             open: BinaryHeap::new(),
+            queue: VecDeque::new(),
             path: Vec::new(),
+            distances: HashMap::new(),
+            stats: Stats::default(),
+            max_nodes: None,
         }
     }
 
+    /// Limits the number of nodes a search may store, to bound its memory
+    /// use. Searches that reach it fail with an error. It does not apply to
+    /// [`AStar::ida_star`], which only stores the current path.
+    pub fn with_node_limit(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
     pub fn into_last_path(self) -> Vec<(N, C)> {
         self.path
     }
 
+    /// Statistics of the last search, whichever kind it was.
+    pub fn last_stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Finds the shortest path like [`AStar::solve`], and returns the
+    /// statistics of the search alongside it.
+    pub fn solve_with_stats<FN, FH, FD, NI>(
+        &mut self,
+        init: N,
+        next: FN,
+        heuristic: FH,
+        is_done: FD,
+    ) -> Result<(Option<&Path<N, C>>, Stats)>
+    where
+        FN: FnMut(&N) -> NI,
+        FH: FnMut(&N) -> C,
+        FD: FnMut(&N) -> bool,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        let found = self.solve(init, next, heuristic, is_done)?.is_some();
+        Ok((found.then_some(&self.path), self.stats))
+    }
+
+    /// Finds the shortest path, `None` if `is_done` accepts no reachable
+    /// node. Fails if the search stores more nodes than allowed by
+    /// [`AStar::with_node_limit`].
     pub fn solve<FN, FH, FD, NI>(
+        &mut self,
+        init: N,
+        next: FN,
+        heuristic: FH,
+        is_done: FD,
+    ) -> Result<Option<&Vec<(N, C)>>>
+    where
+        FN: FnMut(&N) -> NI,
+        FH: FnMut(&N) -> C,
+        FD: FnMut(&N) -> bool,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        self.path.clear();
+        let found = self.search(init, next, heuristic, is_done, None);
+        if let Ok(Some(node)) = &found {
+            self.reconstruct_path(node);
+        }
+        self.open.clear();
+        self.meta.clear();
+        Ok(found?.map(move |_| &self.path))
+    }
+
+    /// Iterative deepening A*, finds the shortest path like [`AStar::solve`]
+    /// while only storing the current path. It repeats a depth-first search
+    /// with an increasing limit on the path and heuristic cost, so it visits
+    /// nodes many times, and the heuristic must never overestimate. Nodes are
+    /// only compared to the current path to avoid cycles.
+    pub fn ida_star<FN, FH, FD, NI>(
         &mut self,
         init: N,
         mut next: FN,
@@ -77,10 +184,194 @@ impl<N: Node, C: Cost> AStar<N, C> {
         FD: FnMut(&N) -> bool,
         NI: IntoIterator<Item = (N, C)>,
     {
+        let started = Instant::now();
+        self.stats = Stats {
+            heuristic_calls: 1,
+            ..Stats::default()
+        };
+        self.path.clear();
+        let mut bound = heuristic(&init);
+        let found = is_done(&init);
+        self.path.push((init.clone(), C::zero()));
+        if found {
+            self.stats.elapsed = started.elapsed();
+            return Some(&self.path);
+        }
+        self.path.clear();
+        // The neighbors left to visit of each node on the path
+        let mut stack: Vec<NI::IntoIter> = Vec::new();
+
+        loop {
+            self.path.push((init.clone(), C::zero()));
+            self.stats.expanded += 1;
+            self.stats.pushed += 1;
+            stack.push(next(&init).into_iter());
+            let mut next_bound: Option<C> = None;
+
+            while let Some(neighbors) = stack.last_mut() {
+                let (node, edge_cost) = match neighbors.next() {
+                    Some(neighbor) => neighbor,
+                    None => {
+                        stack.pop();
+                        self.path.pop();
+                        continue;
+                    }
+                };
+                if self.path.iter().any(|(visited, _)| *visited == node) {
+                    continue;
+                }
+                let path_cost = edge_cost + self.path.last().unwrap().1.clone();
+                self.stats.heuristic_calls += 1;
+                let cost = path_cost.clone() + heuristic(&node);
+                if cost > bound {
+                    if next_bound
+                        .as_ref()
+                        .is_none_or(|next_bound| cost < *next_bound)
+                    {
+                        next_bound = Some(cost);
+                    }
+                    continue;
+                }
+                let is_found = is_done(&node);
+                self.path.push((node, path_cost));
+                self.stats.peak_open = self.stats.peak_open.max(self.path.len());
+                if is_found {
+                    self.stats.elapsed = started.elapsed();
+                    return Some(&self.path);
+                }
+                self.stats.expanded += 1;
+                self.stats.pushed += 1;
+                stack.push(next(&self.path.last().unwrap().0).into_iter());
+            }
+
+            match next_bound {
+                Some(next_bound) => bound = next_bound,
+                // Every path was followed to its end
+                None => {
+                    self.stats.elapsed = started.elapsed();
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Finds the shortest path like [`AStar::solve`], without a heuristic.
+    pub fn dijkstra<FN, FD, NI>(
+        &mut self,
+        init: N,
+        next: FN,
+        is_done: FD,
+    ) -> Result<Option<&Vec<(N, C)>>>
+    where
+        FN: FnMut(&N) -> NI,
+        FD: FnMut(&N) -> bool,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        self.solve(init, next, |_| C::zero(), is_done)
+    }
+
+    /// Every node at most `budget` away from `init`, including `init`
+    /// itself, with the length of the shortest path to it.
+    pub fn reachable_within<FN, NI>(
+        &mut self,
+        init: N,
+        next: FN,
+        budget: C,
+    ) -> Result<&HashMap<N, C>>
+    where
+        FN: FnMut(&N) -> NI,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        self.distances.clear();
+        let result = self.search(init, next, |_| C::zero(), |_| false, Some(&budget));
+        self.distances
+            .extend(self.meta.drain().map(|(node, meta)| (node, meta.path)));
+        self.open.clear();
+        result?;
+        Ok(&self.distances)
+    }
+
+    /// Length of the shortest path from `init` to each of `targets`, `None`
+    /// for those that cannot be reached. Stops once all targets are found.
+    pub fn distances_to<FN, NI>(
+        &mut self,
+        init: N,
+        next: FN,
+        targets: &[N],
+    ) -> Result<Vec<Option<C>>>
+    where
+        FN: FnMut(&N) -> NI,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        let mut remaining: HashSet<&N> = targets.iter().collect();
+        let result = self.search(
+            init,
+            next,
+            |_| C::zero(),
+            |node| {
+                remaining.remove(node);
+                remaining.is_empty()
+            },
+            None,
+        );
+        let distances = targets
+            .iter()
+            .map(|target| {
+                self.meta
+                    .get(target)
+                    .filter(|meta| meta.is_closed)
+                    .map(|meta| meta.path.clone())
+            })
+            .collect();
+        self.open.clear();
+        self.meta.clear();
+        result?;
+        Ok(distances)
+    }
+
+    /// Lengths of the shortest paths between every pair of `nodes`,
+    /// `matrix[from][to]`, found with one search from each of them.
+    pub fn distance_matrix<FN, NI>(
+        &mut self,
+        nodes: &[N],
+        mut next: FN,
+    ) -> Result<Vec<Vec<Option<C>>>>
+    where
+        FN: FnMut(&N) -> NI,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        nodes
+            .iter()
+            .map(|from| self.distances_to(from.clone(), &mut next, nodes))
+            .collect()
+    }
+
+    // Visits nodes in order of their path and heuristic cost until `is_done`
+    // accepts one, which is returned. The metadata of all visited nodes is
+    // left in place, nodes further than `budget` from `init` are skipped.
+    // Fails once it would store more nodes than the limit.
+    fn search<FN, FH, FD, NI>(
+        &mut self,
+        init: N,
+        mut next: FN,
+        mut heuristic: FH,
+        mut is_done: FD,
+        budget: Option<&C>,
+    ) -> Result<Option<N>>
+    where
+        FN: FnMut(&N) -> NI,
+        FH: FnMut(&N) -> C,
+        FD: FnMut(&N) -> bool,
+        NI: IntoIterator<Item = (N, C)>,
+    {
+        let started = Instant::now();
+        self.stats = Stats::default();
         // Used to get FIFO behaviour from the open set
         let mut counter = 0;
-        self.path.clear();
+        self.open.clear();
+        self.meta.clear();
         let init_heuristic = heuristic(&init);
+        self.stats.heuristic_calls += 1;
         let init_meta = Meta {
             is_closed: false,
             path: C::zero(),
@@ -94,6 +385,8 @@ impl<N: Node, C: Cost> AStar<N, C> {
             counter,
         };
         self.open.push(init_open);
+        self.stats.pushed = 1;
+        self.stats.peak_open = 1;
 
         while let Some(open) = self.open.pop() {
             let meta = self.meta.get_mut(&open.node).unwrap();
@@ -106,46 +399,47 @@ impl<N: Node, C: Cost> AStar<N, C> {
             meta.is_closed = true;
 
             if is_done(&open.node) {
-                // Reconstruct the path
-                let mut current_node = Some(&open.node);
-                while let Some(n) = current_node {
-                    let meta = &self.meta[&n];
-                    self.path.push((n.clone(), meta.path.clone()));
-                    current_node = meta.parent.as_ref();
-                }
-
-                self.path.reverse();
-
-                self.open.clear();
-                self.meta.clear();
-                return Some(&self.path);
+                self.stats.elapsed = started.elapsed();
+                return Ok(Some(open.node));
             }
+            self.stats.expanded += 1;
             let path_cost = meta.path.clone();
             for (node, edge_cost) in next(&open.node) {
+                let path_cost = edge_cost + path_cost.clone();
+                if budget.is_some_and(|budget| path_cost > *budget) {
+                    continue;
+                }
                 let cost = match self.meta.get_mut(&node) {
                     Some(meta) => {
                         // If the node was already seen, and is in closed,
                         // the shortest route is already established, and
                         // there is no need to revisit the node.
                         if meta.is_closed {
+                            if path_cost < meta.path {
+                                self.stats.inconsistencies += 1;
+                            }
                             continue;
                         }
                         // If the other node is already in the open set
                         // but the cost through this parent node is cheaper
                         // it has to be updated.
-                        let path_cost = edge_cost + path_cost.clone();
                         if meta.path <= path_cost {
                             continue;
                         }
                         // Update price
                         meta.path = path_cost.clone();
                         meta.parent = Some(open.node.clone());
-                        path_cost
+                        self.stats.reopened += 1;
+                        path_cost + meta.heuristic.clone()
                     }
                     // New node
                     None => {
-                        let path_cost = edge_cost + path_cost.clone();
+                        if self.max_nodes.is_some_and(|max| self.meta.len() >= max) {
+                            self.stats.elapsed = started.elapsed();
+                            return Err(self.limit_error());
+                        }
                         let heuristic_cost = heuristic(&node);
+                        self.stats.heuristic_calls += 1;
                         self.meta.insert(
                             node.clone(),
                             Meta {
@@ -160,16 +454,114 @@ impl<N: Node, C: Cost> AStar<N, C> {
                 };
                 counter += 1;
                 self.open.push(Open {
-                    node: node,
+                    node,
                     cost,
                     counter,
                 });
+                self.stats.pushed += 1;
+                self.stats.peak_open = self.stats.peak_open.max(self.open.len());
             }
         }
 
-        self.open.clear();
+        self.stats.elapsed = started.elapsed();
+        Ok(None)
+    }
+
+    fn limit_error(&self) -> anyhow::Error {
+        anyhow!(
+            "search stopped after storing {} nodes, the most it may use",
+            self.meta.len()
+        )
+    }
+
+    // Follows the parents from `node` back to the start
+    fn reconstruct_path(&mut self, node: &N) {
+        let mut current_node = Some(node);
+        while let Some(n) = current_node {
+            let meta = &self.meta[n];
+            self.path.push((n.clone(), meta.path.clone()));
+            current_node = meta.parent.as_ref();
+        }
+        self.path.reverse();
+    }
+}
+
+impl<N: Node> AStar<N, usize> {
+    /// Breadth-first search for graphs where every edge costs one, returns
+    /// the shortest path like [`AStar::solve`]. Nodes are checked with
+    /// `is_done` as soon as they are found.
+    pub fn bfs<FN, FD, NI>(
+        &mut self,
+        init: N,
+        mut next: FN,
+        mut is_done: FD,
+    ) -> Result<Option<&Vec<(N, usize)>>>
+    where
+        FN: FnMut(&N) -> NI,
+        FD: FnMut(&N) -> bool,
+        NI: IntoIterator<Item = N>,
+    {
+        self.path.clear();
+        self.queue.clear();
         self.meta.clear();
-        None
+        let meta = |path, parent| Meta {
+            is_closed: true,
+            heuristic: 0,
+            path,
+            parent,
+        };
+        let started = Instant::now();
+        self.stats = Stats {
+            pushed: 1,
+            peak_open: 1,
+            ..Stats::default()
+        };
+        let mut found = None;
+        let mut exceeded = false;
+        if is_done(&init) {
+            found = Some(init.clone());
+        }
+        self.meta.insert(init.clone(), meta(0, None));
+        self.queue.push_back(init);
+
+        'search: while found.is_none() {
+            let node = match self.queue.pop_front() {
+                Some(node) => node,
+                None => break,
+            };
+            self.stats.expanded += 1;
+            let depth = self.meta[&node].path + 1;
+            for neighbor in next(&node) {
+                if self.meta.contains_key(&neighbor) {
+                    continue;
+                }
+                if self.max_nodes.is_some_and(|max| self.meta.len() >= max) {
+                    exceeded = true;
+                    break 'search;
+                }
+                self.meta
+                    .insert(neighbor.clone(), meta(depth, Some(node.clone())));
+                if is_done(&neighbor) {
+                    found = Some(neighbor);
+                    break 'search;
+                }
+                self.queue.push_back(neighbor);
+                self.stats.pushed += 1;
+                self.stats.peak_open = self.stats.peak_open.max(self.queue.len());
+            }
+        }
+        self.stats.elapsed = started.elapsed();
+
+        let error = exceeded.then(|| self.limit_error());
+        if let Some(node) = &found {
+            self.reconstruct_path(node);
+        }
+        self.queue.clear();
+        self.meta.clear();
+        match error {
+            Some(error) => Err(error),
+            None => Ok(found.map(move |_| &self.path)),
+        }
     }
 }
 
@@ -177,7 +569,7 @@ impl<N: Node, C: Cost> AStar<N, C> {
 mod tests {
     use super::*;
     use crate::prelude::*;
-    const TEST_FILE: &'static str = include_str!("astar_tests.txt");
+    const TEST_FILE: &str = include_str!("astar_tests.txt");
 
     #[derive(Debug, Clone, PartialEq, Eq)]
     struct TestCase {
@@ -194,8 +586,7 @@ mod tests {
 
         fn maze_char1(s: &str) -> IResult<&str, &str> {
             let mut matched_count = 0;
-            let mut chars = s.chars();
-            while let Some(c) = chars.next() {
+            for c in s.chars() {
                 if c == '#' || c == '.' || c == 'S' || c == 'E' {
                     matched_count += 1;
                 } else {
@@ -204,7 +595,7 @@ mod tests {
             }
 
             if matched_count == 0 {
-                Err(nom::Err::Error((s, ErrorKind::Many1)))
+                Err(nom::Err::Error(nom::error::Error::new(s, ErrorKind::Many1)))
             } else {
                 Ok((&s[matched_count..], &s[0..matched_count]))
             }
@@ -274,8 +665,26 @@ mod tests {
         .1
     }
 
+    // The open cells next to `pos`
+    fn maze_neighbors(maze: &Mat2<bool>, pos: Vec2us) -> ArrayVec<Vec2us, 4> {
+        let mut next = ArrayVec::new();
+        if pos.x > 0 && !maze[pos.x - 1][pos.y] {
+            next.push((pos.x - 1, pos.y).into());
+        }
+        if pos.x < maze.width() - 1 && !maze[pos.x + 1][pos.y] {
+            next.push((pos.x + 1, pos.y).into());
+        }
+        if pos.y > 0 && !maze[pos.x][pos.y - 1] {
+            next.push((pos.x, pos.y - 1).into());
+        }
+        if pos.y < maze.height() - 1 && !maze[pos.x][pos.y + 1] {
+            next.push((pos.x, pos.y + 1).into());
+        }
+        next
+    }
+
     #[test]
-    fn astar_test_file() {
+    fn astar_test_file() -> Result<()> {
         let mut astar = AStar::new();
         for TestCase {
             name,
@@ -288,31 +697,13 @@ mod tests {
             println!("Pathfinding: {}", name);
             let solution = astar.solve(
                 start,
-                |&pos| {
-                    let mut next: ArrayVec<[Vec2us; 4]> = ArrayVec::new();
-
-                    if pos.x > 0 && !maze[pos.x - 1][pos.y] {
-                        next.push((pos.x - 1, pos.y).into());
-                    }
-                    if pos.x < maze.width() - 1 && !maze[pos.x + 1][pos.y] {
-                        next.push((pos.x + 1, pos.y).into());
-                    }
-
-                    if pos.y > 0 && !maze[pos.x][pos.y - 1] {
-                        next.push((pos.x, pos.y - 1).into());
-                    }
-                    if pos.y < maze.height() - 1 && !maze[pos.x][pos.y + 1] {
-                        next.push((pos.x, pos.y + 1).into());
-                    }
-
-                    next.into_iter().map(|n| (n, 1))
-                },
+                |&pos| maze_neighbors(&maze, pos).into_iter().map(|n| (n, 1)),
                 |&pos| {
                     let delta = end.delta(pos);
                     delta.x + delta.y
                 },
                 |&pos| pos == end,
-            );
+            )?;
             if let Some(path) = solution {
                 println!("Found path of length: {}", path.last().unwrap().1);
             } else {
@@ -321,5 +712,199 @@ mod tests {
             let found_path_length = solution.map(|path| path.last().unwrap().1);
             assert_eq!(path_length, found_path_length);
         }
+        Ok(())
+    }
+
+    #[test]
+    fn search_modes() -> Result<()> {
+        let mut astar = AStar::new();
+        for TestCase {
+            maze,
+            start,
+            end,
+            path_length,
+            ..
+        } in test_cases()
+        {
+            let next = |&pos: &Vec2us| maze_neighbors(&maze, pos);
+            let weighted = |pos: &Vec2us| next(pos).into_iter().map(|n| (n, 1));
+            let length =
+                |path: Option<&Vec<(Vec2us, usize)>>| path.map(|path| path.last().unwrap().1);
+
+            let bfs = astar.bfs(start, next, |&pos| pos == end)?;
+            assert_eq!(length(bfs), path_length);
+            if let Some(path) = bfs {
+                assert_eq!(path.first().unwrap().0, start);
+                assert_eq!(path.len() - 1, path.last().unwrap().1);
+            }
+            let dijkstra = astar.dijkstra(start, weighted, |&pos| pos == end)?;
+            assert_eq!(length(dijkstra), path_length);
+
+            // The end is reachable within exactly the length of the path
+            let distances =
+                astar.reachable_within(start, weighted, path_length.unwrap_or(usize::MAX))?;
+            assert_eq!(distances.get(&end).copied(), path_length);
+            assert_eq!(distances[&start], 0);
+            let targets = [end, start];
+            assert_eq!(
+                astar.distances_to(start, weighted, &targets)?,
+                [path_length, Some(0)]
+            );
+            assert_eq!(
+                astar.distance_matrix(&targets, weighted)?,
+                [[Some(0), path_length], [path_length, Some(0)]]
+            );
+            if let Some(path_length) = path_length.filter(|&length| length > 0) {
+                let distances = astar.reachable_within(start, weighted, path_length - 1)?;
+                assert!(!distances.contains_key(&end));
+                assert!(distances.values().all(|&distance| distance < path_length));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stats() -> Result<()> {
+        let mut astar = AStar::new();
+        for TestCase {
+            maze, start, end, ..
+        } in test_cases()
+        {
+            let next = |&pos: &Vec2us| maze_neighbors(&maze, pos).into_iter().map(|n| (n, 1));
+            let (path, stats) = astar.solve_with_stats(
+                start,
+                next,
+                |&pos| {
+                    let delta = end.delta(pos);
+                    delta.x + delta.y
+                },
+                |&pos| pos == end,
+            )?;
+            let path = path.cloned();
+            assert_eq!(stats, astar.last_stats());
+            assert_eq!(stats.heuristic_calls, stats.pushed - stats.reopened);
+            assert!(stats.expanded <= stats.pushed);
+            assert!(stats.peak_open <= stats.pushed);
+            if let Some(path) = &path {
+                assert!(stats.expanded >= path.len() - 1);
+            }
+
+            // Without a heuristic at least as many nodes are expanded
+            astar.dijkstra(start, next, |&pos| pos == end)?;
+            let dijkstra = astar.last_stats();
+            assert_eq!(
+                dijkstra.heuristic_calls,
+                dijkstra.pushed - dijkstra.reopened
+            );
+            assert!(dijkstra.expanded >= stats.expanded);
+
+            astar.bfs(start, |&pos| maze_neighbors(&maze, pos), |&pos| pos == end)?;
+            let bfs = astar.last_stats();
+            assert_eq!((bfs.heuristic_calls, bfs.reopened), (0, 0));
+            assert!(bfs.expanded <= dijkstra.expanded);
+        }
+
+        // The heuristic overestimates the cost of `1` compared to `0`, so `2`
+        // is closed before the shorter path to it through `1` is found
+        let graph: [&[(u32, u32)]; 4] = [&[(1, 1), (2, 3)], &[(2, 1)], &[(3, 10)], &[]];
+        let mut astar = AStar::new();
+        let (path, stats) = astar.solve_with_stats(
+            0,
+            |&node| graph[node as usize].iter().copied(),
+            |&node| if node == 1 { 10 } else { 0 },
+            |&node| node == 3,
+        )?;
+        assert_eq!(path.unwrap().last(), Some(&(3, 13)));
+        assert_eq!((stats.expanded, stats.inconsistencies), (3, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn reopened_nodes() -> Result<()> {
+        // The heuristic is consistent. `2` is first reached directly, then
+        // through `1`, and once `3` and `4` are expanded through `4`, which is
+        // the shortest path. Until then `2` must wait for its turn, including
+        // its heuristic, or it is closed with the path through `1`.
+        let graph: [&[(u32, u32)]; 6] = [
+            &[(1, 1), (2, 10), (3, 1)],
+            &[(2, 5)],
+            &[(5, 100)],
+            &[(4, 1)],
+            &[(2, 1)],
+            &[],
+        ];
+        let heuristic = [0, 0, 50, 10, 9, 0];
+        let mut astar = AStar::new();
+        let (path, stats) = astar.solve_with_stats(
+            0,
+            |&node| graph[node as usize].iter().copied(),
+            |&node| heuristic[node as usize],
+            |&node| node == 5,
+        )?;
+        assert_eq!(path.unwrap(), &[(0, 0), (3, 1), (4, 2), (2, 3), (5, 103)]);
+        assert_eq!((stats.reopened, stats.inconsistencies), (2, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn bounded_memory() -> Result<()> {
+        let mut astar = AStar::new();
+        for TestCase {
+            maze,
+            start,
+            end,
+            path_length,
+            ..
+        } in test_cases()
+        {
+            let next = |&pos: &Vec2us| maze_neighbors(&maze, pos);
+            let weighted = |pos: &Vec2us| next(pos).into_iter().map(|n| (n, 1));
+            let heuristic = |&pos: &Vec2us| {
+                let delta = end.delta(pos);
+                delta.x + delta.y
+            };
+
+            let path = astar
+                .ida_star(start, weighted, heuristic, |&pos| pos == end)
+                .cloned();
+            assert_eq!(
+                path.as_ref().map(|path| path.last().unwrap().1),
+                path_length
+            );
+            if let Some(path) = path {
+                assert_eq!(path[0], (start, 0));
+                assert!(path.windows(2).all(|pair| pair[1].1 == pair[0].1 + 1));
+                assert_eq!(astar.last_stats().peak_open, path.len());
+            }
+
+            // The limit is only reached by searches that need more nodes
+            let mut limited = AStar::new().with_node_limit(20);
+            let found = limited
+                .solve(start, weighted, heuristic, |&pos| pos == end)
+                .map(|path| path.cloned());
+            let (path, stats) =
+                astar.solve_with_stats(start, weighted, heuristic, |&pos| pos == end)?;
+            if stats.pushed - stats.reopened <= 20 {
+                assert_eq!(found?.as_ref(), path);
+            } else {
+                assert_eq!(
+                    found.unwrap_err().to_string(),
+                    "search stopped after storing 20 nodes, the most it may use"
+                );
+            }
+
+            // Reaching the limit is an error, not a missing path
+            let mut limited = AStar::new().with_node_limit(3);
+            assert!(limited.bfs(start, next, |&pos| pos == end).is_err());
+            assert!(limited
+                .dijkstra(start, weighted, |&pos| pos == end)
+                .is_err());
+            assert!(limited
+                .reachable_within(start, weighted, usize::MAX)
+                .is_err());
+            assert!(limited.distances_to(start, weighted, &[end]).is_err());
+            assert!(limited.distance_matrix(&[start, end], weighted).is_err());
+        }
+        Ok(())
     }
 }
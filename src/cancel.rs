@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag used to ask a running solution to stop early.
+///
+/// The runner installs a token for the current thread before running a part,
+/// solutions with long loops poll it through [`check`] or [`is_cancelled`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Installs the token polled by [`check`] and [`is_cancelled`] on this thread.
+pub fn set_current(token: Option<CancellationToken>) {
+    CURRENT.with(|current| *current.borrow_mut() = token);
+}
+
+/// Whether the part running on this thread has been asked to stop.
+pub fn is_cancelled() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    })
+}
+
+/// Returns an error if the part running on this thread has been asked to stop.
+pub fn check() -> Result<()> {
+    if is_cancelled() {
+        Err(anyhow!("cancelled"))
+    } else {
+        Ok(())
+    }
+}
+
+#[test]
+fn cancel() -> Result<()> {
+    check()?;
+    let token = CancellationToken::new();
+    set_current(Some(token.clone()));
+    check()?;
+    token.cancel();
+    assert!(is_cancelled());
+    assert!(check().is_err());
+    set_current(None);
+    check()?;
+
+    Ok(())
+}
@@ -0,0 +1,452 @@
+use crate::registry::{day_number, ParamOverride};
+use crate::report::Format;
+use advent_of_code_2016::assembunny::Dialect;
+use anyhow::{anyhow, Context, Result};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: advent_of_code_2016 [OPTIONS] [SELECTOR...]
+
+Selectors (all days are run if none are given):
+  day11              every part of a day
+  day11:pt2          a single part of a day
+  day10..day15       an inclusive range of days
+  day10..day15:pt1   a single part of every day in a range
+
+Options:
+  --input <path>     read the puzzle input from <path> instead of ./data/dayXX.txt
+                     and ./data/dayXX/*.txt
+  --stdin            read the puzzle input from standard input
+  --param <param>    override a parameter of the selected parts, as
+                     [dayXX:][part:]name=value, e.g. day16:pt2:disk_size=272
+  --threads <n>      number of worker threads (default: one less than the CPU count)
+  --timeout <secs>   report a part as timed out once it runs longer than <secs>
+  --bench <n>        run every selected part <n> times and report min/median/max
+  --format <format>  output format: text (default), json (JSON lines) or csv
+  --output <path>    write json or csv output to <path> instead of standard output
+  --answers <path>   compare answers against <path> instead of ./data/answers.txt
+  --check            exit with a non-zero status if any answer does not match,
+                     only the inputs in ./data have answers
+  --record           write the answers of all successful parts to the answers file
+  --list             print all registered days, parts and parameters, then exit
+  --debug            step through the input of a single day as an assembunny
+                     program (days 12, 23 and 25), see help at its prompt
+  --profile          run the assembunny program of a single day, then print
+                     how often each instruction ran and the loops it took,
+                     stops at --timeout
+  --trace <path>     with --profile, write every step to <path> as
+                     <step> <instruction> <a> <b> <c> <d>
+  --analyze          derive the registers at exit of the assembunny program of
+                     a single day from its initial registers, and print its
+                     blocks and loops
+  --registers <regs> initial registers for --debug and --profile, e.g. a=7,c=1,
+                     with --analyze the registers that are known
+  --in <values>      values read by in instructions, e.g. 3,-1,4
+  --extended         allow the add, mul, nop and in extension instructions
+  --register-file <n>
+                     number of registers, a onwards (default: 4)
+  --help             print this message, then exit";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    days: RangeInclusive<u32>,
+    part: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    DataDirectory,
+    File(PathBuf),
+    Stdin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    List,
+    Debug,
+    Profile,
+    Analyze,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub command: Command,
+    pub selectors: Vec<Selector>,
+    pub input: InputSource,
+    pub params: Vec<ParamOverride>,
+    /// Initial register values as (register, value).
+    pub registers: Vec<(usize, i64)>,
+    pub in_values: Vec<i64>,
+    pub dialect: Dialect,
+    pub trace: Option<PathBuf>,
+    pub threads: Option<usize>,
+    pub runs: usize,
+    pub timeout: Option<Duration>,
+    pub format: Format,
+    pub output: Option<PathBuf>,
+    pub answers: PathBuf,
+    pub check: bool,
+    pub record: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Run,
+            selectors: Vec::new(),
+            input: InputSource::DataDirectory,
+            params: Vec::new(),
+            registers: Vec::new(),
+            in_values: Vec::new(),
+            dialect: Dialect::PUZZLE,
+            trace: None,
+            threads: None,
+            runs: 1,
+            timeout: None,
+            format: Format::Text,
+            output: None,
+            answers: crate::answers::DEFAULT_PATH.into(),
+            check: false,
+            record: false,
+        }
+    }
+}
+
+impl Selector {
+    pub fn matches(&self, module_name: &str, part_name: &str) -> bool {
+        match day_number(module_name) {
+            Some(day) if self.days.contains(&day) => {
+                self.part.as_ref().is_none_or(|part| part == part_name)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (days, part) = match s.split_once(':') {
+            Some((days, part)) if !part.is_empty() => (days, Some(part.to_owned())),
+            Some(_) => return Err(anyhow!("missing part name in selector {:?}", s)),
+            None => (s, None),
+        };
+        let parse_day = |day: &str| {
+            day_number(day).ok_or_else(|| anyhow!("expected a day such as day07, got {:?}", day))
+        };
+        let days = match days.split_once("..") {
+            Some((from, to)) => {
+                let (from, to) = (parse_day(from)?, parse_day(to)?);
+                if from > to {
+                    return Err(anyhow!("empty day range in selector {:?}", s));
+                }
+                from..=to
+            }
+            None => {
+                let day = parse_day(days)?;
+                day..=day
+            }
+        };
+        Ok(Selector { days, part })
+    }
+}
+
+// Parses registers given as a=7,c=1
+fn parse_registers(s: &str) -> Result<Vec<(usize, i64)>> {
+    s.split(',')
+        .map(|assignment| {
+            let (name, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected register=value, got {:?}", assignment))?;
+            let register = match name.as_bytes() {
+                &[c] if c.is_ascii_lowercase() => (c - b'a') as usize,
+                _ => return Err(anyhow!("unknown register {:?}", name)),
+            };
+            let value = value
+                .parse()
+                .with_context(|| format!("invalid value {:?} for register {}", value, name))?;
+            Ok((register, value))
+        })
+        .collect()
+}
+
+impl Options {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        fn number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T> {
+            value
+                .ok_or_else(|| anyhow!("{} expects a value", flag))?
+                .parse()
+                .map_err(|_| anyhow!("{} expects a positive number", flag))
+        }
+
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--help" | "-h" => options.command = Command::Help,
+                "--list" => options.command = Command::List,
+                "--debug" => options.command = Command::Debug,
+                "--profile" => options.command = Command::Profile,
+                "--analyze" => options.command = Command::Analyze,
+                "--trace" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--trace expects a path"))?;
+                    options.trace = Some(path.into());
+                }
+                "--registers" => {
+                    let registers = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--registers expects values such as a=7,c=1"))?;
+                    options.registers = parse_registers(&registers)?;
+                }
+                "--in" => {
+                    let values = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--in expects values such as 3,-1,4"))?;
+                    options.in_values = values
+                        .split(',')
+                        .map(|value| {
+                            value
+                                .parse()
+                                .map_err(|_| anyhow!("--in expects numbers, got {:?}", value))
+                        })
+                        .collect::<Result<_>>()?;
+                }
+                "--extended" => options.dialect.extensions = true,
+                "--register-file" => match number("--register-file", args.next())? {
+                    registers @ 1..=26 => options.dialect.registers = registers,
+                    _ => return Err(anyhow!("--register-file expects a number from 1 to 26")),
+                },
+                "--stdin" => options.input = InputSource::Stdin,
+                "--check" => options.check = true,
+                "--record" => options.record = true,
+                "--answers" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--answers expects a path"))?;
+                    options.answers = path.into();
+                }
+                "--input" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--input expects a path"))?;
+                    options.input = InputSource::File(path.into());
+                }
+                "--param" => {
+                    let param = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--param expects name=value"))?;
+                    options.params.push(param.parse()?);
+                }
+                "--threads" => match number("--threads", args.next())? {
+                    0 => return Err(anyhow!("--threads expects a positive number")),
+                    threads => options.threads = Some(threads),
+                },
+                "--format" => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--format expects a format"))?;
+                    options.format = format.parse()?;
+                }
+                "--output" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--output expects a path"))?;
+                    options.output = Some(path.into());
+                }
+                "--timeout" => {
+                    let secs: f64 = number("--timeout", args.next())?;
+                    if !(secs > 0.0 && secs.is_finite()) {
+                        return Err(anyhow!("--timeout expects a positive number"));
+                    }
+                    options.timeout = Some(Duration::from_secs_f64(secs));
+                }
+                "--bench" => match number("--bench", args.next())? {
+                    0 => return Err(anyhow!("--bench expects a positive number")),
+                    runs => options.runs = runs,
+                },
+                flag if flag.starts_with("--") => return Err(anyhow!("unknown option {}", flag)),
+                selector => options.selectors.push(
+                    selector
+                        .parse()
+                        .with_context(|| format!("invalid selector {:?}", selector))?,
+                ),
+            }
+        }
+
+        if options.record && !options.params.is_empty() {
+            return Err(anyhow!("--record cannot be combined with --param"));
+        }
+        if options.record && options.input != InputSource::DataDirectory {
+            return Err(anyhow!(
+                "--record cannot be combined with --input or --stdin"
+            ));
+        }
+        if let Command::Debug | Command::Profile | Command::Analyze = options.command {
+            match options.selectors.as_slice() {
+                [selector] if selector.days.start() == selector.days.end() => {}
+                _ => {
+                    return Err(anyhow!(
+                        "--debug, --profile and --analyze expect a single day, e.g. day23"
+                    ))
+                }
+            }
+        }
+        if let Some(&(register, _)) = options
+            .registers
+            .iter()
+            .find(|&&(register, _)| register >= options.dialect.registers)
+        {
+            return Err(anyhow!(
+                "register {} is out of range, there are {} registers",
+                (b'a' + register as u8) as char,
+                options.dialect.registers
+            ));
+        }
+        if options.trace.is_some() && options.command != Command::Profile {
+            return Err(anyhow!("--trace requires --profile"));
+        }
+        if options.output.is_some() && options.format == Format::Text {
+            return Err(anyhow!("--output requires --format json or csv"));
+        }
+
+        Ok(options)
+    }
+
+    /// The day selected for `--debug`, `--profile` or `--analyze`.
+    pub fn debug_day(&self) -> Option<u32> {
+        match (&self.command, self.selectors.as_slice()) {
+            (Command::Debug | Command::Profile | Command::Analyze, [selector]) => {
+                Some(*selector.days.start())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_selected(&self, module_name: &str, part_name: &str) -> bool {
+        self.selectors.is_empty()
+            || self
+                .selectors
+                .iter()
+                .any(|selector| selector.matches(module_name, part_name))
+    }
+
+    /// Returns the first selector that does not match any of the given
+    /// `(module_name, part_name)` pairs, which is most likely a typo.
+    pub fn unmatched_selector<'a, I>(&self, parts: I) -> Option<&Selector>
+    where
+        I: Iterator<Item = (&'a str, &'a str)> + Clone,
+    {
+        self.selectors.iter().find(|selector| {
+            !parts
+                .clone()
+                .any(|(module_name, part_name)| selector.matches(module_name, part_name))
+        })
+    }
+}
+
+impl std::fmt::Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "day{:02}", self.days.start())?;
+        if self.days.start() != self.days.end() {
+            write!(f, "..day{:02}", self.days.end())?;
+        }
+        if let Some(part) = &self.part {
+            write!(f, ":{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn cli() -> Result<()> {
+    fn args(args: &[&str]) -> Result<Options> {
+        Options::from_args(args.iter().map(|&arg| arg.to_owned()))
+    }
+
+    let options = args(&["day1", "day10..day12:pt2", "--threads", "3", "--stdin"])?;
+    assert_eq!(options.threads, Some(3));
+    assert_eq!(options.input, InputSource::Stdin);
+    assert!(options.is_selected("day01", "pt1"));
+    assert!(!options.is_selected("day11", "pt1"));
+    assert!(options.is_selected("day11", "pt2"));
+    assert!(!options.is_selected("day13", "pt2"));
+
+    let options = args(&["--input", "alt.txt", "--bench", "5", "--timeout", "1.5"])?;
+    assert_eq!(options.input, InputSource::File("alt.txt".into()));
+    assert_eq!(options.runs, 5);
+    assert_eq!(options.timeout, Some(Duration::from_millis(1500)));
+    assert!(options.is_selected("day25", "pt"));
+
+    let options = args(&["--format", "csv", "--output", "out.csv"])?;
+    assert_eq!(options.format, Format::Csv);
+    assert_eq!(options.output, Some("out.csv".into()));
+    assert!(!options.check && !options.record);
+
+    let options = args(&["--check", "--answers", "other.txt"])?;
+    assert!(options.check);
+    assert_eq!(options.answers, PathBuf::from("other.txt"));
+
+    let options = args(&["--param", "day16:pt2:disk_size=20", "--param", "rows=10"])?;
+    assert_eq!(options.params[0].day, Some(16));
+    assert_eq!(options.params[0].part_name.as_deref(), Some("pt2"));
+    assert_eq!(options.params[1].name, "rows");
+    assert_eq!(options.params[1].value, "10");
+
+    assert_eq!(args(&["--list"])?.command, Command::List);
+    let options = args(&["--debug", "day23", "--input", "alt.txt"])?;
+    assert_eq!(options.command, Command::Debug);
+    assert!(options.is_selected("day23", "pt1"));
+    assert!(args(&["--debug"]).is_err());
+    let options = args(&[
+        "--profile",
+        "day12",
+        "--registers",
+        "c=1,a=-2",
+        "--trace",
+        "t",
+    ])?;
+    assert_eq!(options.command, Command::Profile);
+    assert_eq!(options.registers, vec![(2, 1), (0, -2)]);
+    assert!(args(&["day12", "--trace", "t"]).is_err());
+    assert!(args(&["--profile", "day12", "--registers", "e=1"]).is_err());
+    let options = args(&[
+        "--debug",
+        "day12",
+        "--registers",
+        "e=1",
+        "--register-file",
+        "5",
+        "--extended",
+        "--in",
+        "3,-1",
+    ])?;
+    assert_eq!(options.dialect, Dialect::EXTENDED.with_registers(5));
+    assert_eq!(options.in_values, vec![3, -1]);
+    assert!(args(&["--debug", "day12", "--register-file", "27"]).is_err());
+    assert!(args(&["--profile", "day12", "--registers", "a"]).is_err());
+    assert!(args(&["--debug", "day12..day23"]).is_err());
+    let options = args(&["--analyze", "day12", "--registers", "c=1"])?;
+    assert_eq!(options.command, Command::Analyze);
+    assert_eq!(options.debug_day(), Some(12));
+    assert!(args(&["--analyze", "day12", "day23"]).is_err());
+    assert!(args(&["--output", "out.txt"]).is_err());
+    assert!(args(&["--format", "xml"]).is_err());
+    assert!(args(&["day12..day10"]).is_err());
+    assert!(args(&["day1:"]).is_err());
+    assert!(args(&["--bench", "0"]).is_err());
+    assert!(args(&["--timeout", "-1"]).is_err());
+    assert!(args(&["--frobnicate"]).is_err());
+    assert!(args(&["--param", "rows"]).is_err());
+    assert!(args(&["--record", "--param", "rows=1"]).is_err());
+    assert!(args(&["--record", "--stdin"]).is_err());
+    assert!(args(&["--record", "--input", "alt.txt"]).is_err());
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+#[aoc(day = 1, part = 1, parser = parse)]
 pub fn pt1(input: Vec<Action>) -> Result<i32> {
     let (_, dx, dy) =
         input
@@ -12,6 +13,7 @@ pub fn pt1(input: Vec<Action>) -> Result<i32> {
     Ok(dx.abs() + dy.abs())
 }
 
+#[aoc(day = 1, part = 2, parser = parse)]
 pub fn pt2(input: Vec<Action>) -> Result<i32> {
     let mut visited = HashSet::new();
     let mut facing = Facing::North;
@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+#[aoc(day = 2, part = 1, parser = parse)]
 pub fn pt1(input: Vec<Vec<Direction>>) -> Result<String> {
     let mut x = 1u8;
     let mut y = 1u8;
@@ -15,14 +16,15 @@ pub fn pt1(input: Vec<Vec<Direction>>) -> Result<String> {
                 _ => {}
             }
         }
-        out.push((x + y * 3 + '1' as u8) as char);
+        out.push((x + y * 3 + b'1') as char);
     }
 
     Ok(out)
 }
 
+#[aoc(day = 2, part = 2, parser = parse)]
 pub fn pt2(input: Vec<Vec<Direction>>) -> Result<String> {
-    const KEYPAD: &'static [u8] =b"\
+    const KEYPAD: &[u8] =b"\
 -------\
 ---1---\
 --234--\
@@ -85,7 +87,7 @@ RRDDD"
         ]
     );
 
-    const TEST_INPUT: &'static str = r"
+    const TEST_INPUT: &str = r"
 ULL
 RRDDD
 LURDL
@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+#[aoc(day = 3, part = 1, parser = parse)]
 pub fn pt1(input: Vec<(u32, u32, u32)>) -> Result<usize> {
     Ok(input
         .into_iter()
@@ -7,10 +8,11 @@ pub fn pt1(input: Vec<(u32, u32, u32)>) -> Result<usize> {
         .count())
 }
 
+#[aoc(day = 3, part = 2, parser = parse)]
 pub fn pt2(input: Vec<(u32, u32, u32)>) -> Result<usize> {
     let mut new_input = Vec::new();
     for i in 0..input.len() / 3 {
-        let a = input[i * 3 + 0];
+        let a = input[i * 3];
         let b = input[i * 3 + 1];
         let c = input[i * 3 + 2];
         new_input.push((a.0, b.0, c.0));
@@ -28,6 +28,7 @@ impl Room {
     }
 }
 
+#[aoc(day = 4, part = 1, parser = parse)]
 pub fn pt1(input: Vec<Room>) -> Result<u32> {
     Ok(input
         .into_iter()
@@ -36,12 +37,12 @@ pub fn pt1(input: Vec<Room>) -> Result<u32> {
         .sum())
 }
 
+#[aoc(day = 4, part = 2, parser = parse)]
 pub fn pt2(input: Vec<Room>) -> Result<u32> {
     input
         .into_iter()
         .filter(|room| room.compute_checksum() == room.checksum)
-        .filter(|room| room.decrypt_name() == "northpole object storage")
-        .next()
+        .find(|room| room.decrypt_name() == "northpole object storage")
         .map(|room| room.sector_id)
         .ok_or(anyhow!("no north pole objects room found"))
 }
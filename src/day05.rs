@@ -15,9 +15,10 @@ pub fn write_u64_to_buffer(buffer: &mut Vec<u8>, mut nr: u64) {
         }
         nr /= 10;
     }
-    (&mut buffer[start_len..]).reverse();
+    buffer[start_len..].reverse();
 }
 
+#[aoc(day = 5, part = 1)]
 pub fn pt1(input: &str) -> Result<String> {
     let mut out = String::with_capacity(8);
 
@@ -26,6 +27,9 @@ pub fn pt1(input: &str) -> Result<String> {
         buffer.push(c as u8);
     }
     for i in 0u64.. {
+        if i % 0x10000 == 0 {
+            cancel::check()?;
+        }
         buffer.truncate(input.len());
         write_u64_to_buffer(&mut buffer, i);
 
@@ -40,6 +44,7 @@ pub fn pt1(input: &str) -> Result<String> {
     unreachable!()
 }
 
+#[aoc(day = 5, part = 2)]
 pub fn pt2(input: &str) -> Result<String> {
     let mut out = [' '; 8];
     let mut fill_count = 0;
@@ -49,6 +54,9 @@ pub fn pt2(input: &str) -> Result<String> {
         buffer.push(c as u8);
     }
     for i in 0u64.. {
+        if i % 0x10000 == 0 {
+            cancel::check()?;
+        }
         buffer.truncate(input.len());
         write_u64_to_buffer(&mut buffer, i);
 
@@ -20,9 +20,11 @@ fn pt_impl(input: Vec<String>, count_multiplier: i32) -> Result<String> {
 
 }
 
+#[aoc(day = 6, part = 1, parser = parse)]
 pub fn pt1(input: Vec<String>) -> Result<String> {
     pt_impl(input, 1)
 }
+#[aoc(day = 6, part = 2, parser = parse)]
 pub fn pt2(input: Vec<String>) -> Result<String> {
     pt_impl(input, -1)
 }
@@ -36,7 +38,7 @@ pub fn parse(s: &str) -> IResult<&str, Vec<String>> {
 fn day06() -> Result<()> {
     test_parse!(parse, "abcdef\nghijkl" => vec!["abcdef".to_owned(), "ghijkl".to_owned()]);
 
-    const EXAMPLE: &'static str = "\
+    const EXAMPLE: &str = "\
 eedadn
 drvtee
 eandsr
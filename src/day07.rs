@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+#[aoc(day = 7, part = 1, parser = parse)]
 pub fn pt1(input: Vec<Ip7>) -> Result<usize> {
     Ok(input
         .into_iter()
@@ -31,6 +32,7 @@ impl Ip7Part {
     }
 }
 
+#[aoc(day = 7, part = 2, parser = parse)]
 pub fn pt2(input: Vec<Ip7>) -> Result<usize> {
     Ok(input.into_iter().filter(supports_ssl).count())
 }
@@ -55,7 +57,7 @@ fn supports_ssl(ip: &Ip7) -> bool {
         abas.clear();
         abas.extend((0..=supernet.len() - 3).filter_map(|i| is_aba(&supernet[i..i + 3])));
 
-        if abas.len() == 0 {
+        if abas.is_empty() {
             continue;
         }
         for hypernet in &hypernets {
@@ -8,16 +8,16 @@ impl Screen {
     }
 
     fn apply(&mut self, instruction: &Instruction) {
-        match instruction {
-            &Instruction::Rect(Vec2us { x, y }) => {
+        match *instruction {
+            Instruction::Rect(Vec2us { x, y }) => {
                 for cx in 0..x {
                     let column = &mut self.0[cx];
-                    for cy in 0..y {
-                        column[cy] = true;
+                    for cell in column.iter_mut().take(y) {
+                        *cell = true;
                     }
                 }
             }
-            &Instruction::RotateRow { row, amount } => {
+            Instruction::RotateRow { row, amount } => {
                 let amount = amount % self.0.width();
                 // PERF: Could be optimized to run in O(width)
                 //       currently runs in O(width * amount).
@@ -29,7 +29,7 @@ impl Screen {
                     }
                 }
             }
-            &Instruction::RotateColumn { column, amount } => {
+            Instruction::RotateColumn { column, amount } => {
                 let column = &mut self.0[column];
                 column.rotate_right(amount % column.len());
             }
@@ -48,13 +48,14 @@ impl Display for Screen {
                 }
             }
             if y != self.0.height() - 1 {
-                write!(f, "\n")?;
+                writeln!(f)?;
             }
         }
         Ok(())
     }
 }
 
+#[aoc(day = 8, part = both, parser = parse)]
 pub fn pts(input: Vec<Instruction>) -> Result<Parts> {
     let mut screen = Screen::new(50, 6);
     for instruction in &input {
@@ -38,10 +38,12 @@ fn decompress(s: &str) -> Result<String> {
     Ok(out)
 }
 
+#[aoc(day = 9, part = 1)]
 pub fn pt1(input: &str) -> Result<usize> {
     Ok(decompress(input)?.len())
 }
 
+#[aoc(day = 9, part = 2)]
 pub fn pt2(input: &str) -> Result<u64> {
     use parsers::*;
 
@@ -51,7 +53,7 @@ pub fn pt2(input: &str) -> Result<u64> {
         Repetition(u64, &'s str),
     }
 
-    fn parse_sections(s: &str) -> Result<Vec<Section>> {
+    fn parse_sections(s: &str) -> Result<Vec<Section<'_>>> {
         let (_, sections) = many0(alt((
             map(alpha1, |s: &str| Section::Text(s.len() as u64)),
             map(
@@ -80,7 +82,7 @@ pub fn pt2(input: &str) -> Result<u64> {
         Ok(sum)
     }
 
-    Ok(calc_len(input)?)
+    calc_len(input)
 }
 
 #[test]
@@ -2,7 +2,12 @@
 
 use crate::prelude::*;
 
-pub fn pts((inits, instrs): (Vec<Initializer>, HashMap<usize, Instruction>)) -> Result<Parts> {
+#[aoc(day = 10, part = both, parser = parse, params(low_chip = 17, high_chip = 61))]
+pub fn pts(
+    (inits, instrs): (Vec<Initializer>, HashMap<usize, Instruction>),
+    low_chip: u32,
+    high_chip: u32,
+) -> Result<Parts> {
     let mut outputs = HashMap::new();
     let mut bots = HashMap::new();
 
@@ -41,13 +46,13 @@ pub fn pts((inits, instrs): (Vec<Initializer>, HashMap<usize, Instruction>)) ->
                 Some(b) => b,
                 None => continue,
             };
-            let instr = match instrs.get(&bot) {
+            let instr = match instrs.get(bot) {
                 Some(instr) => instr,
                 None => continue,
             };
 
             let (a, b) = (*a.min(b), *a.max(b));
-            if a == 17 && b == 61 {
+            if a == low_chip && b == high_chip {
                 pt1 = Some(*bot);
             }
             break Some((*bot, instr.low_to, a, instr.high_to, b));
@@ -61,7 +66,8 @@ pub fn pts((inits, instrs): (Vec<Initializer>, HashMap<usize, Instruction>)) ->
         }
     }
 
-    Ok((pt1.unwrap(), outputs[&0] * outputs[&1] * outputs[&2]).to_parts())
+    let pt1 = pt1.ok_or_else(|| anyhow!("no bot compares chips {} and {}", low_chip, high_chip))?;
+    Ok((pt1, outputs[&0] * outputs[&1] * outputs[&2]).to_parts())
 }
 
 pub fn parse(s: &str) -> IResult<&str, (Vec<Initializer>, HashMap<usize, Instruction>)> {
@@ -105,7 +111,7 @@ pub fn parse(s: &str) -> IResult<&str, (Vec<Initializer>, HashMap<usize, Instruc
             }
             acc
         },
-    )(&s)
+    )(s)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -123,7 +129,7 @@ pub struct Instruction {
 
 #[test]
 fn day10() -> Result<()> {
-    const EXAMPLE: &'static str = "\
+    const EXAMPLE: &str = "\
 value 5 goes to bot 2
 bot 2 gives low to bot 1 and high to bot 0
 value 3 goes to bot 1
@@ -141,6 +147,7 @@ value 2 goes to bot 2";
     );
 
     test_parse!(parse, EXAMPLE => example);
+    test_part!(parse, |input| pts(input, 2, 5), EXAMPLE => (2, 30).to_parts());
 
     Ok(())
 }
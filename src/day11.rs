@@ -1,81 +1,41 @@
 use crate::prelude::*;
 
-trait FacilityBounds = Clone + Eq + Ord + std::hash::Hash;
+trait FacilityBounds: Clone + Eq + Ord + std::hash::Hash {}
+impl<T: Clone + Eq + Ord + std::hash::Hash> FacilityBounds for T {}
 
-fn solve<const N: usize>(input: Vec<Vec<Module>>) -> Result<usize>
+fn solve<const N: usize>(input: Vec<Vec<Module>>, max_nodes: usize) -> Result<usize>
 where
     [Element; N]: Default,
     [bool; N]: Default,
 {
-    use std::{collections::VecDeque, rc::Rc};
-
     let facility: Facility<[Element; N]> = Facility::from_input(input)?;
-    struct TrackedFacility<E: FacilityBounds> {
-        parent: Option<Rc<Self>>,
-        current: Facility<E>,
-    }
-    impl<const N: usize> TrackedFacility<[Element; N]>
-    where
-        [Element; N]: Default,
-        [bool; N]: Default,
-    {
-        pub fn depth(&self) -> usize {
-            match self.parent.as_ref() {
-                Some(parent) => parent.depth() + 1,
-                None => 0,
-            }
-        }
-    }
-
-    let mut visited = HashSet::new();
-    visited.insert(facility.clone());
-    let mut bfs = VecDeque::new();
-    bfs.push_back(Rc::new(TrackedFacility {
-        parent: None,
-        current: facility,
-    }));
-
-    while let Some(tracked) = bfs.pop_front() {
-        for facility in tracked.current.next_configurations() {
-            if !visited.insert(facility.clone()) {
-                continue;
-            }
-            let child = Rc::new(TrackedFacility {
-                parent: Some(Rc::clone(&tracked)),
-                current: facility,
-            });
-            if child.current.is_solved() {
-                let depth = child.depth();
-                // let mut current = Some(&child);
-                // let mut current_depth = depth as isize;
-                // while let Some(tracked) = current {
-                //     println!("depth {}\n{:#?}\n", current_depth, tracked.current);
-                //     current_depth -= 1;
-                //     current = tracked.parent.as_ref();
-                // }
-                return Ok(depth);
-            }
-            bfs.push_back(child);
-        }
-    }
-
-    Err(anyhow!("no solution found"))
+    let mut astar = crate::astar::AStar::new().with_node_limit(max_nodes);
+    let path = astar
+        .bfs(
+            facility,
+            |facility| facility.next_configurations(),
+            |facility| facility.is_solved(),
+        )?
+        .ok_or_else(|| anyhow!("no solution found"))?;
+    Ok(path.len() - 1)
 }
 
-pub fn pt1(input: Vec<Vec<Module>>) -> Result<usize> {
-    solve::<5>(input)
+#[aoc(day = 11, part = 1, parser = parse, params(max_nodes = 10000000))]
+pub fn pt1(input: Vec<Vec<Module>>, max_nodes: usize) -> Result<usize> {
+    solve::<5>(input, max_nodes)
 }
 
-pub fn pt2(mut input: Vec<Vec<Module>>) -> Result<usize> {
+#[aoc(day = 11, part = 2, parser = parse, params(max_nodes = 10000000))]
+pub fn pt2(mut input: Vec<Vec<Module>>, max_nodes: usize) -> Result<usize> {
     input[0].push(Module::Generator("elerium"));
     input[0].push(Module::Microchip("elerium"));
     input[0].push(Module::Generator("dilithium"));
     input[0].push(Module::Microchip("dilithium"));
-    solve::<7>(input)
+    solve::<7>(input, max_nodes)
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
-#[repr(packed)]
+#[repr(C, packed)]
 struct Element(u8);
 
 impl Element {
@@ -196,7 +156,7 @@ where
         (0..FLOOR_COUNT as usize).all(|i| !has_generator[i] || !has_unpaired_microchip[i])
     }
 
-    fn next_configurations<'s>(&'s self) -> impl Iterator<Item = Facility<[Element; N]>> + 's {
+    fn next_configurations(&self) -> impl Iterator<Item = Facility<[Element; N]>> {
         use std::iter::ExactSizeIterator;
         #[derive(Clone, Copy)]
         struct NextFloors {
@@ -248,31 +208,30 @@ where
             Microchip(u8),
             Generator(u8),
         }
+        let this = self.clone();
         let elevator_position = self.elevator_position;
-        let moveable_items = self
-            .elements
-            .iter()
-            .enumerate()
-            .flat_map(move |(idx, elem)| {
-                let idx = idx as u8;
-                let mut array: ArrayVec<Moveable, 2> = ArrayVec::new();
-                unsafe {
-                    if elem.generator() == elevator_position {
-                        array.push_unchecked(Moveable::Generator(idx));
-                    }
-                    if elem.microchip() == elevator_position {
-                        array.push_unchecked(Moveable::Microchip(idx));
+        let moveable_items =
+            IntoIterator::into_iter(self.elements)
+                .enumerate()
+                .flat_map(move |(idx, elem)| {
+                    let idx = idx as u8;
+                    let mut array: ArrayVec<Moveable, 2> = ArrayVec::new();
+                    unsafe {
+                        if elem.generator() == elevator_position {
+                            array.push_unchecked(Moveable::Generator(idx));
+                        }
+                        if elem.microchip() == elevator_position {
+                            array.push_unchecked(Moveable::Microchip(idx));
+                        }
                     }
-                }
-                array.into_iter()
-            });
+                    array.into_iter()
+                });
 
         let moveable_pairs = moveable_items
             .clone()
             .enumerate()
             .flat_map(move |(count, a)| {
-                repeat(None)
-                    .take(1)
+                std::iter::repeat_n(None, 1)
                     .chain(moveable_items.clone().skip(count + 1).map(Option::Some))
                     .map(move |b| (a, b))
             });
@@ -280,18 +239,17 @@ where
         moveable_pairs
             .flat_map(move |(a, b)| next_floors.map(move |f| (a, b, f)))
             .filter_map(move |(a, b, next_floor)| {
-                let mut new = self.clone();
+                let mut new = this.clone();
                 new.elevator_position = next_floor;
 
-                #[rustfmt::skip] match a {
-                    Moveable::Generator(idx) => new.elements[idx as usize].move_generator(next_floor),
-                    Moveable::Microchip(idx) => new.elements[idx as usize].move_microchip(next_floor),
-                }
-
-                if let Some(b) = b {
-                    #[rustfmt::skip] match b {
-                        Moveable::Generator(idx) => new.elements[idx as usize].move_generator(next_floor),
-                        Moveable::Microchip(idx) => new.elements[idx as usize].move_microchip(next_floor),
+                for moveable in std::iter::once(a).chain(b) {
+                    match moveable {
+                        Moveable::Generator(idx) => {
+                            new.elements[idx as usize].move_generator(next_floor)
+                        }
+                        Moveable::Microchip(idx) => {
+                            new.elements[idx as usize].move_microchip(next_floor)
+                        }
                     }
                 }
 
@@ -311,7 +269,7 @@ impl Debug for Element {
     }
 }
 
-pub fn parse(s: &str) -> IResult<&str, Vec<Vec<Module>>> {
+pub fn parse(s: &str) -> IResult<&str, Vec<Vec<Module<'_>>>> {
     use parsers::*;
     map_res(
         fold_many1(
@@ -349,7 +307,7 @@ pub fn parse(s: &str) -> IResult<&str, Vec<Vec<Module>>> {
                 ),
                 terminated(char('.'), opt(line_ending)),
             ),
-            || HashMap::new(),
+            HashMap::new,
             |mut acc, (floor, modules)| {
                 acc.insert(floor, modules);
                 acc
@@ -389,14 +347,20 @@ impl<'s> Module<'s> {
 
 #[test]
 fn day11() -> Result<()> {
-    fn solve_2(input: Vec<Vec<Module>>) -> Result<usize> {
-        solve::<2>(input)
-    }
-    test_part!(parse, solve_2, "\
+    const EXAMPLE: &str = "\
 The first floor contains a hydrogen-compatible microchip and a lithium-compatible microchip.
 The second floor contains a hydrogen generator.
 The third floor contains a lithium generator.
-The fourth floor contains nothing relevant." => 11);
+The fourth floor contains nothing relevant.";
+    fn solve_2(input: Vec<Vec<Module>>) -> Result<usize> {
+        solve::<2>(input, 1000)
+    }
+    test_part!(parse, solve_2, EXAMPLE => 11);
+    let (_, input) = parse(EXAMPLE)?;
+    assert_eq!(
+        solve::<2>(input, 10).unwrap_err().to_string(),
+        "search stopped after storing 10 nodes, the most it may use"
+    );
 
     Ok(())
 }
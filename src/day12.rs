@@ -1,18 +1,24 @@
 use crate::assembunny::*;
 use crate::prelude::*;
 
-pub fn pt1(program: Vec<Instruction>) -> Result<i64> {
+#[aoc(day = 12, part = 1, parser = parse, params(backend = "compiled", max_steps = 1000000000))]
+pub fn pt1(program: Vec<Instruction>, backend: Backend, max_steps: u64) -> Result<i64> {
     let mut regs = Registers::default();
     let mut program = Program::new(program)?;
-    program.run_to_end(&mut regs, |_| {});
+    program
+        .run_bounded(backend, &mut regs, max_steps, |_| {})
+        .halted()?;
     Ok(regs[0])
 }
 
-pub fn pt2(program: Vec<Instruction>) -> Result<i64> {
+#[aoc(day = 12, part = 2, parser = parse, params(backend = "compiled", max_steps = 1000000000))]
+pub fn pt2(program: Vec<Instruction>, backend: Backend, max_steps: u64) -> Result<i64> {
     let mut regs = Registers::default();
     regs[2] = 1;
     let mut program = Program::new(program)?;
-    program.run_to_end(&mut regs, |_| {});
+    program
+        .run_bounded(backend, &mut regs, max_steps, |_| {})
+        .halted()?;
     Ok(regs[0])
 }
 
@@ -31,7 +37,7 @@ pub fn parse(s: &str) -> IResult<&str, Vec<Instruction>> {
 
 #[test]
 fn day12() -> Result<()> {
-    const EXAMPLE: &'static str = "\
+    const EXAMPLE: &str = "\
 cpy 41 a
 inc a
 inc a
@@ -48,7 +54,20 @@ dec a";
     ];
     test_parse!(parse, EXAMPLE => example);
 
-    test_part!(pt1, example.clone() => 42);
+    test_part!(|input| pt1(input, Backend::Compiled, 100), example.clone() => 42);
+    test_part!(|input| pt1(input, Backend::Interpreter, 100), example.clone() => 42);
+    let looping = vec![Instruction::JumpIfNotZero(
+        Value::Constant(1),
+        Value::Constant(0),
+    )];
+    assert!(pt1(looping, Backend::Compiled, 1_000_000)
+        .unwrap_err()
+        .to_string()
+        .starts_with("the program loops forever"));
+    assert_eq!(
+        pt1(example, Backend::Compiled, 3).unwrap_err().to_string(),
+        "the program did not halt within 3 steps"
+    );
 
     Ok(())
 }
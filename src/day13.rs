@@ -1,55 +1,40 @@
 use crate::prelude::*;
 
-pub fn pt1(input: usize) -> Result<usize> {
+#[aoc(day = 13, part = 1, parser = parse, params(target_x = 31, target_y = 39))]
+pub fn pt1(input: usize, target_x: usize, target_y: usize) -> Result<usize> {
     let is_free = is_free_fn(input);
     let mut astar = crate::astar::AStar::new();
     let path = astar
         .solve(
             Vec2us::new(1, 1),
             move |pos: &Vec2us| Neighbors(*pos, 0).filter(is_free).zip(repeat(1)),
-            |pos: &Vec2us| ((pos.x as isize) - 31).abs() + ((pos.y as isize) - 39).abs(),
-            |pos: &Vec2us| pos.x == 31 && pos.y == 39,
-        )
+            |pos: &Vec2us| {
+                ((pos.x as isize) - target_x as isize).abs()
+                    + ((pos.y as isize) - target_y as isize).abs()
+            },
+            |pos: &Vec2us| pos.x == target_x && pos.y == target_y,
+        )?
         .ok_or_else(|| anyhow!("no path found"))?;
     Ok(path.len() - 1)
 }
 
+#[aoc(day = 13, part = 2, parser = parse)]
 pub fn pt2(input: usize) -> Result<usize> {
     let is_free = is_free_fn(input);
-    let mut map = HashMap::new();
-    fn add_to_map<F>(
-        map: &mut HashMap<Vec2us, usize>,
-        pos: Vec2us,
-        dist_from_start: usize,
-        is_free: F,
-    ) where
-        F: Fn(&Vec2us) -> bool + Clone + Copy,
-    {
-        if dist_from_start == 50 {
-            return;
-        }
-        for neighbor in Neighbors(pos, 0).filter(is_free) {
-            use std::collections::hash_map::Entry;
-            match map.entry(neighbor) {
-                Entry::Occupied(mut entry) if *entry.get() > dist_from_start => {
-                    *entry.get_mut() = dist_from_start + 1;
-                }
-                Entry::Vacant(entry) => {
-                    entry.insert(dist_from_start + 1);
-                }
-                Entry::Occupied(_) => continue,
-            }
-            add_to_map(map, neighbor, dist_from_start + 1, is_free);
-        }
-    }
-    map.insert(Vec2us::new(1, 1), 0);
-    add_to_map(&mut map, Vec2us::new(1, 1), 0, is_free);
-    Ok(map.len())
+    let mut astar = crate::astar::AStar::new();
+    let reachable = astar.reachable_within(
+        Vec2us::new(1, 1),
+        move |pos: &Vec2us| Neighbors(*pos, 0).filter(is_free).zip(repeat(1)),
+        50,
+    )?;
+    Ok(reachable.len())
 }
 
-fn is_free_fn(designer_nr: usize) -> impl Fn(&Vec2us) -> bool + Clone + Copy {
+fn is_free_fn(designer_nr: usize) -> impl Fn(&Vec2us) -> bool + Copy {
     move |p| {
-        (p.x * p.x + 3 * p.x + 2 * p.x * p.y + p.y + p.y * p.y + designer_nr).count_ones() % 2 == 0
+        (p.x * p.x + 3 * p.x + 2 * p.x * p.y + p.y + p.y * p.y + designer_nr)
+            .count_ones()
+            .is_multiple_of(2)
     }
 }
 
@@ -135,11 +120,12 @@ fn day13() -> Result<()> {
             move |pos: &Vec2us| Neighbors(*pos, 0).filter(is_free).zip(repeat(1)),
             |pos: &Vec2us| ((pos.x as isize) - 7).abs() + ((pos.y as isize) - 4).abs(),
             |pos: &Vec2us| pos.x == 7 && pos.y == 4,
-        )
+        )?
         .expect("no path found");
     assert_eq!(path.len(), 12);
     assert_eq!(path[11].0, Vec2us::new(7, 4));
     assert_eq!(path[11].1, 11);
+    test_part!(parse, |input| pt1(input, 7, 4), "10" => 11);
 
     Ok(())
 }
@@ -1,7 +1,7 @@
 use crate::prelude::*;
 use md5::Digest;
 
-fn solve<F>(input: &str, mut f: F) -> u64
+fn solve<F>(input: &str, mut f: F) -> Result<u64>
 where
     F: FnMut(&[u8]) -> Digest,
 {
@@ -11,12 +11,15 @@ where
     let mut index_to_count_next = [0; 16];
     let mut three_repetitions: Vec<(u64, u8)> = Vec::new();
     let mut valid_keys = Vec::with_capacity(128);
-    let mut remainder = std::u64::MAX;
+    let mut remainder = u64::MAX;
     for i in 0u64.. {
         remainder -= 1;
         if remainder == 0 {
             break;
         }
+        if i % 0x400 == 0 {
+            cancel::check()?;
+        }
         buffer.truncate(input.len());
         crate::day05::write_u64_to_buffer(&mut buffer, i);
         let Digest(bytes) = f(&buffer);
@@ -62,7 +65,7 @@ where
             .zip(bytes.iter().skip(1))
             .filter_map(|(a, b)| {
                 let c = a & 0x0f;
-                if (c == (a >> 4) && c == (b >> 4)) || (c == (b >> 4) && c == (b & 0x0f)) {
+                if c == (b >> 4) && (c == (a >> 4) || c == (b & 0x0f)) {
                     Some(c)
                 } else {
                     None
@@ -75,26 +78,28 @@ where
     }
 
     valid_keys.sort();
-    valid_keys[63]
+    Ok(valid_keys[63])
 }
 
+#[aoc(day = 14, part = 1)]
 pub fn pt1(input: &str) -> Result<u64> {
-    Ok(solve(input, |v| md5::compute(v)))
+    solve(input, |v| md5::compute(v))
 }
 
+#[aoc(day = 14, part = 2)]
 pub fn pt2(input: &str) -> Result<u64> {
     let mut digest_buf = [0; 32];
-    Ok(solve(input, |v| {
+    solve(input, |v| {
         let mut current_digest = md5::compute(v);
         for _ in 0..2016 {
             for i in 0..16 {
-                digest_buf[i * 2 + 0] = crate::day05::byte_to_hex(current_digest[i] >> 4) as u8;
+                digest_buf[i * 2] = crate::day05::byte_to_hex(current_digest[i] >> 4) as u8;
                 digest_buf[i * 2 + 1] = crate::day05::byte_to_hex(current_digest[i] & 0x0f) as u8;
             }
-            current_digest = md5::compute(&digest_buf);
+            current_digest = md5::compute(digest_buf);
         }
         current_digest
-    }))
+    })
 }
 
 #[test]
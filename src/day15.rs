@@ -1,6 +1,7 @@
 use crate::prelude::*;
 use num::Integer;
 
+#[aoc(day = 15, part = 1, parser = parse)]
 pub fn pt1(input: Vec<Disc>) -> Result<u64> {
     let mut lcm = 1;
     let mut start_time = 0;
@@ -11,7 +12,7 @@ pub fn pt1(input: Vec<Disc>) -> Result<u64> {
         // `start_time` must advance `remainder`, but is only allowed to increment
         // in multiples of `lcm` (otherwise previous discs would no longer be valid).
         let mut increment = remainder;
-        while increment % lcm != 0 {
+        while !increment.is_multiple_of(lcm) {
             increment += disc.size;
         }
         start_time += increment;
@@ -20,6 +21,7 @@ pub fn pt1(input: Vec<Disc>) -> Result<u64> {
     Ok(start_time)
 }
 
+#[aoc(day = 15, part = 2, parser = parse)]
 pub fn pt2(mut input: Vec<Disc>) -> Result<u64> {
     input.push(Disc {
         size: 11,
@@ -41,7 +43,7 @@ pub fn parse(s: &str) -> IResult<&str, Vec<Disc>> {
             ),
             terminated(u64_str, pair(char('.'), opt(line_ending))),
         )),
-        || Vec::new(),
+        Vec::new,
         |mut v, (idx, size, current)| {
             v.push(Disc { size, current });
             assert_eq!(idx, v.len());
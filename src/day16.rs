@@ -2,7 +2,7 @@ use crate::prelude::*;
 use bitvec::prelude::*;
 
 fn solve(disc_size: usize, seed: BitVec) -> Result<String> {
-    if disc_size % 2 != 0 {
+    if !disc_size.is_multiple_of(2) {
         return Err(anyhow!("disc size must be even"));
     }
     let mut buf: BitVec = seed;
@@ -25,7 +25,7 @@ fn solve(disc_size: usize, seed: BitVec) -> Result<String> {
     // Checksum
     loop {
         for i in 0..buf.len() / 2 {
-            let checksum_digit = buf[i * 2 + 0] == buf[i * 2 + 1];
+            let checksum_digit = buf[i * 2] == buf[i * 2 + 1];
             buf.as_mut_bitslice().set(i, checksum_digit);
         }
         buf.truncate(buf.len() / 2);
@@ -38,12 +38,14 @@ fn solve(disc_size: usize, seed: BitVec) -> Result<String> {
     Ok(buf.into_iter().map(|b| if b { '1' } else { '0' }).collect())
 }
 
-pub fn pt1(input: BitVec) -> Result<String> {
-    solve(272, input)
+#[aoc(day = 16, part = 1, parser = parse, params(disk_size = 272))]
+pub fn pt1(input: BitVec, disk_size: usize) -> Result<String> {
+    solve(disk_size, input)
 }
 
-pub fn pt2(input: BitVec) -> Result<String> {
-    solve(35651584, input)
+#[aoc(day = 16, part = 2, parser = parse, params(disk_size = 35651584))]
+pub fn pt2(input: BitVec, disk_size: usize) -> Result<String> {
+    solve(disk_size, input)
 }
 
 pub fn parse(s: &str) -> IResult<&str, BitVec> {
@@ -56,7 +58,7 @@ pub fn parse(s: &str) -> IResult<&str, BitVec> {
 
 #[test]
 fn day16() -> Result<()> {
-    test_part!(parse, |input| solve(20, input), "10000" => "01100");
+    test_part!(parse, |input| pt1(input, 20), "10000" => "01100");
 
     Ok(())
 }
@@ -56,7 +56,7 @@ impl Node {
         };
         let nx = nx as u64;
         let ny = ny as u64;
-        let mut new_node = self.clone();
+        let mut new_node = *self;
         const CLEAR_MASK: u64 = !((Node::MASK_LEN << Node::SHIFT_LEN)
             | (Node::MASK_POS << Node::SHIFT_X)
             | (Node::MASK_POS << Node::SHIFT_Y));
@@ -133,6 +133,7 @@ impl ExactSizeIterator for PathIter {
     }
 }
 
+#[aoc(day = 17, part = 1, parser = parse)]
 pub fn pt1(input: Vec<u8>) -> Result<String> {
     let mut astar = crate::astar::AStar::new();
 
@@ -149,7 +150,9 @@ pub fn pt1(input: Vec<u8>) -> Result<String> {
         }));
     }
 
-    let path = astar.solve(
+    // Every path leads to a different state, so there is nothing to gain
+    // from storing them, iterative deepening only keeps the current one
+    let path = astar.ida_star(
         Node::new(),
         |&n| {
             buf.truncate(base_len);
@@ -194,6 +197,7 @@ pub fn pt1(input: Vec<u8>) -> Result<String> {
     }
 }
 
+#[aoc(day = 17, part = 2, parser = parse)]
 pub fn pt2(input: Vec<u8>) -> Result<usize> {
     let base_len = input.len();
     let mut longest = base_len;
@@ -33,12 +33,14 @@ fn solve(mut row: Vec<Tile>, height: usize) -> Result<usize> {
     Ok(safe_tile_count)
 }
 
-pub fn pt1(input: Vec<Tile>) -> Result<usize> {
-    solve(input, 40)
+#[aoc(day = 18, part = 1, parser = parse, params(rows = 40))]
+pub fn pt1(input: Vec<Tile>, rows: usize) -> Result<usize> {
+    solve(input, rows)
 }
 
-pub fn pt2(input: Vec<Tile>) -> Result<usize> {
-    solve(input, 400000)
+#[aoc(day = 18, part = 2, parser = parse, params(rows = 400000))]
+pub fn pt2(input: Vec<Tile>, rows: usize) -> Result<usize> {
+    solve(input, rows)
 }
 
 pub fn parse(s: &str) -> IResult<&str, Vec<Tile>> {
@@ -54,7 +56,7 @@ pub enum Tile {
 
 #[test]
 fn day18() -> Result<()> {
-    test_part!(parse, |i| solve(i, 10), ".^^.^.^^^^" => 38);
+    test_part!(parse, |i| pt1(i, 10), ".^^.^.^^^^" => 38);
 
     Ok(())
 }
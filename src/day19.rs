@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+#[aoc(day = 19, part = 1, parser = parse)]
 pub fn pt1(input: u32) -> Result<u32> {
     if input == 0 {
         return Err(anyhow!("0 is invalid"));
@@ -8,10 +9,11 @@ pub fn pt1(input: u32) -> Result<u32> {
     // every time a power of two is encountered.
     // Consequently, if you subtract the highest power of two, what remains
     // is an index into a sequence of all the positive odd numbers.
-    let idx = input & !(1 << 31 - input.leading_zeros());
+    let idx = input & !(1 << (31 - input.leading_zeros()));
     Ok(idx * 2 + 1)
 }
 
+#[aoc(day = 19, part = 2, parser = parse)]
 pub fn pt2(input: u32) -> Result<u32> {
     if input == 0 {
         return Err(anyhow!("0 is invalid"));
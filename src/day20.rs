@@ -12,6 +12,7 @@ impl Debug for Range {
     }
 }
 
+#[aoc(day = 20, part = 1, parser = parse)]
 pub fn pt1(mut ranges: Vec<Range>) -> Result<u32> {
     ranges.sort_unstable();
     let mut lowest_allowed = 0;
@@ -46,18 +47,19 @@ fn coalesce_ranges(mut old: Vec<Range>) -> Vec<Range> {
     new
 }
 
+#[aoc(day = 20, part = 2, parser = parse)]
 pub fn pt2(ranges: Vec<Range>) -> Result<u32> {
     let ranges = coalesce_ranges(ranges);
 
     let mut total = 0;
     if ranges[0].from != 0 {
-        total += ranges[0].from - 1;
+        total += ranges[0].from;
     }
     for (a, b) in ranges.iter().zip(ranges.iter().skip(1)) {
         total += b.from - a.to - 1;
     }
-    if ranges.last().unwrap().to != std::u32::MAX {
-        total += std::u32::MAX - ranges.last().unwrap().to;
+    if ranges.last().unwrap().to != u32::MAX {
+        total += u32::MAX - ranges.last().unwrap().to;
     }
 
     Ok(total)
@@ -78,7 +80,10 @@ fn day20() -> Result<()> {
     test_part!(parse, pt2, "\
 5-8
 0-2
-4-7" => std::u32::MAX - 8);
+4-7" => u32::MAX - 7);
+    test_part!(parse, pt2, "\
+2-8
+10-4294967295" => 3);
 
     Ok(())
 }
@@ -82,14 +82,19 @@ fn unscramble(instructions: &[Instruction], data: &mut [u8]) {
     }
 }
 
-pub fn pt1(input: Vec<Instruction>) -> Result<String> {
-    let mut data: Vec<_> = b"abcdefgh".iter().cloned().collect();
+#[aoc(day = 21, part = 1, parser = parse, params(password = "abcdefgh"))]
+pub fn pt1(input: Vec<Instruction>, password: String) -> Result<String> {
+    let mut data = password.into_bytes();
     scramble(&input, data.as_mut_slice());
     Ok(String::from_utf8(data).unwrap())
 }
 
-pub fn pt2(input: Vec<Instruction>) -> Result<String> {
-    let mut data: Vec<_> = b"fbgdceah".iter().cloned().collect();
+#[aoc(day = 21, part = 2, parser = parse, params(scrambled = "fbgdceah"))]
+pub fn pt2(input: Vec<Instruction>, scrambled: String) -> Result<String> {
+    if scrambled.len() != 8 {
+        return Err(anyhow!("can only unscramble passwords of 8 letters"));
+    }
+    let mut data = scrambled.into_bytes();
     unscramble(&input, data.as_mut_slice());
     Ok(String::from_utf8(data).unwrap())
 }
@@ -167,7 +172,7 @@ fn day21() -> Result<()> {
             Err(parsers::Err::Incomplete(_)) => unreachable!(),
         }
     }
-    let buf = &mut b"abcde".iter().cloned().collect::<Vec<u8>>();
+    let buf = &mut b"abcde".to_vec();
     // Scramble
     scramble(&[instr("swap position 4 with position 0")], buf);
     assert_eq!(buf, b"ebcda");
@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 type Vec2 = crate::vec2::Vec2<u16>;
 
+#[aoc(day = 22, part = 1, parser = parse)]
 pub fn pt1(input: Vec<Node>) -> Result<usize> {
     // In order to perform this in O(n log n) as opposed to O(n^2)
     // we first create a separate list which just contains all the
@@ -66,6 +67,7 @@ fn cell_neighbors(cells: &Mat2<Cell>, pos: Vec2us) -> impl Iterator<Item = Vec2u
     neighbors.into_iter()
 }
 
+#[aoc(day = 22, part = 2, parser = parse)]
 pub fn pt2(input: Vec<Node>) -> Result<u32> {
     // Verify the input
     use crate::vec2::AabbIteratorEx;
@@ -83,7 +85,7 @@ pub fn pt2(input: Vec<Node>) -> Result<u32> {
         cells[Vec2us::from(node.pos)] = Cell {
             size: node.avail + node.used,
             used: node.used,
-            dist: std::u32::MAX,
+            dist: u32::MAX,
         };
     }
     let empty_pos = {
@@ -116,14 +118,14 @@ pub fn pt2(input: Vec<Node>) -> Result<u32> {
     let max_used = cells
         .data
         .iter()
-        .filter(|node| node.dist != std::u32::MAX)
+        .filter(|node| node.dist != u32::MAX)
         .map(|node| node.used)
         .max()
         .ok_or_else(|| anyhow!("no reachable nodes"))?;
     let min_size = cells
         .data
         .iter()
-        .filter(|node| node.dist != std::u32::MAX)
+        .filter(|node| node.dist != u32::MAX)
         .map(|node| node.size)
         .min()
         .ok_or_else(|| anyhow!("no reachable nodes"))?;
@@ -135,7 +137,7 @@ pub fn pt2(input: Vec<Node>) -> Result<u32> {
 
     // Check that all the nodes on the top 2 rows are reachable
     if !(0..cells.width())
-        .all(|x| cells[x][0].dist != std::u32::MAX && cells[x][1].dist != std::u32::MAX)
+        .all(|x| cells[x][0].dist != u32::MAX && cells[x][1].dist != u32::MAX)
     {
         return Err(anyhow!("expect top two rows to be free"));
     }
@@ -1,19 +1,23 @@
 use crate::assembunny::*;
 use crate::prelude::*;
 
-pub fn pt1(input: Vec<Instruction>) -> Result<i64> {
+#[aoc(day = 23, part = 1, parser = parse, params(a = 7, backend = "compiled", max_steps = 1000000000))]
+pub fn pt1(input: Vec<Instruction>, a: i64, backend: Backend, max_steps: u64) -> Result<i64> {
     let mut prog = Program::new(input)?;
     let mut reg = Registers::default();
-    reg[0] = 7;
-    prog.run_to_end(&mut reg, |_| {});
+    reg[0] = a;
+    prog.run_bounded(backend, &mut reg, max_steps, |_| {})
+        .halted()?;
     Ok(reg[0])
 }
 
-pub fn pt2(input: Vec<Instruction>) -> Result<i64> {
+#[aoc(day = 23, part = 2, parser = parse, params(a = 12, backend = "compiled", max_steps = 1000000000))]
+pub fn pt2(input: Vec<Instruction>, a: i64, backend: Backend, max_steps: u64) -> Result<i64> {
     let mut prog = Program::new(input)?;
     let mut reg = Registers::default();
-    reg[0] = 12;
-    prog.run_to_end(&mut reg, |_| {});
+    reg[0] = a;
+    prog.run_bounded(backend, &mut reg, max_steps, |_| {})
+        .halted()?;
     Ok(reg[0])
 }
 
@@ -21,9 +25,8 @@ pub fn parse(s: &str) -> IResult<&str, Vec<Instruction>> {
     use parsers::*;
     map_res(parse_assembunny, |instrs| {
         for instr in &instrs {
-            match instr {
-                Instruction::Out(_) => return Err(()),
-                _ => {}
+            if let Instruction::Out(_) = instr {
+                return Err(());
             }
         }
         Ok(instrs)
@@ -32,7 +35,8 @@ pub fn parse(s: &str) -> IResult<&str, Vec<Instruction>> {
 
 #[test]
 fn day23() -> Result<()> {
-    test_part!(parse, pt1, "\
+    for &backend in &[Backend::Compiled, Backend::Interpreter] {
+        test_part!(parse, |input| pt1(input, 7, backend, 100), "\
 cpy 2 a
 tgl a
 tgl a
@@ -40,6 +44,7 @@ tgl a
 cpy 1 a
 dec a
 dec a" => 3);
+    }
 
     Ok(())
 }
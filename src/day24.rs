@@ -1,5 +1,6 @@
 use crate::astar::AStar;
 use crate::prelude::*;
+use crate::tsp;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Map {
@@ -24,30 +25,6 @@ fn neighbors(pos: Vec2us) -> [Vec2us; 4] {
     ]
 }
 
-fn pathfind(
-    astar: &mut AStar<Vec2us, usize>,
-    from: Vec2us,
-    to: Vec2us,
-    map: &Map,
-) -> Result<usize> {
-    let path = astar
-        .solve(
-            from,
-            |&pos| {
-                IntoIterator::into_iter(neighbors(pos))
-                    .filter(|&new_pos| !map.is_wall(new_pos))
-                    .map(|new_pos| (new_pos, 1))
-            },
-            |&pos| {
-                let delta = pos.delta(to);
-                delta.x + delta.y
-            },
-            |&pos| pos == to,
-        )
-        .ok_or_else(|| anyhow!("no path found"))?;
-    Ok(path.last().unwrap().1)
-}
-
 impl Display for Map {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         use fmt::Write;
@@ -77,51 +54,29 @@ impl Display for Map {
 
 // This day is a variation of the Travelling Salesman Problem, which is a
 // well known problem in computer science. There is currently no known
-// algorithm to find an exact solution in a better time than O(n^2*2^n).
-// However, since the amount of places to evaluate is only 8 in the input
-// I've chosen to perform a brute-force approach, with the one optimization
-// being that I precompute all the path lengths between the places.
+// algorithm to find an exact solution in a better time than O(n^2*2^n),
+// which `tsp` achieves with Held-Karp over the precomputed path lengths
+// between the places.
+#[aoc(day = 24, part = both, parser = parse)]
 pub fn pts(map: Map) -> Result<Parts> {
     let mut astar = AStar::new();
 
     // First pre-compute the length to go from any of the points of interest
-    // to any of the other points of interest. This is O(n^2).
-    let mut path_lengths: Vec<Vec<usize>> = Vec::with_capacity(map.points_of_interest.len());
-    for i in 0..map.points_of_interest.len() {
-        let mut from_this_point = Vec::with_capacity(map.points_of_interest.len());
-        for j in 0..i {
-            from_this_point.push(path_lengths[j][i]);
-        }
-        from_this_point.push(0);
-        for j in i + 1..map.points_of_interest.len() {
-            from_this_point.push(pathfind(
-                &mut astar,
-                map.points_of_interest[i],
-                map.points_of_interest[j],
-                &map,
-            )?);
-        }
-        path_lengths.push(from_this_point);
-    }
-
-    // Then permute the points of interest, and calculate the sum of the path sections
-    // for each permutation. This is O(N!).
-    let mut min_path_len = (std::usize::MAX, std::usize::MAX);
-    for permutation in
-        (1..map.points_of_interest.len()).permutations(map.points_of_interest.len() - 1)
-    {
-        let mut from = 0;
-        let mut total_len = 0;
-        for to in permutation {
-            total_len += path_lengths[from][to];
-            from = to;
-        }
-        min_path_len.0 = min_path_len.0.min(total_len);
-        total_len += path_lengths[from][0];
-        min_path_len.1 = min_path_len.1.min(total_len);
-    }
+    // to any of the other points of interest, with one search from each.
+    let path_lengths = astar
+        .distance_matrix(&map.points_of_interest, |&pos| {
+            IntoIterator::into_iter(neighbors(pos))
+                .filter(|&new_pos| !map.is_wall(new_pos))
+                .map(|new_pos| (new_pos, 1))
+        })?
+        .into_iter()
+        .map(|lengths| lengths.into_iter().collect::<Option<Vec<usize>>>())
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("no path found"))?;
 
-    Ok(min_path_len.to_parts())
+    let path = tsp::shortest_path(&path_lengths, 0)?;
+    let tour = tsp::shortest_tour(&path_lengths, 0)?;
+    Ok((path.cost, tour.cost).to_parts())
 }
 
 pub fn parse(s: &str) -> IResult<&str, Map> {
@@ -144,7 +99,7 @@ pub fn parse(s: &str) -> IResult<&str, Map> {
     let grid = separated_list1(line_ending, row);
     map_res(grid, |rows: Vec<Vec<Cell>>| {
         let height = rows.len();
-        if height < 3 || height > 64 {
+        if !(3..=64).contains(&height) {
             return Err(anyhow!("map height must be in 3..=64 but is {}", height));
         }
         let width = rows[0].len();
@@ -171,8 +126,7 @@ pub fn parse(s: &str) -> IResult<&str, Map> {
                         let n = n as usize;
                         map.columns[x] |= 1 << y;
                         if map.points_of_interest.len() < n + 1 {
-                            map.points_of_interest
-                                .resize(n + 1, (std::usize::MAX).into());
+                            map.points_of_interest.resize(n + 1, (usize::MAX).into());
                         }
                         map.points_of_interest[n] = (x, y).into();
                     }
@@ -183,7 +137,7 @@ pub fn parse(s: &str) -> IResult<&str, Map> {
         if map
             .points_of_interest
             .iter()
-            .any(|v| *v == (std::usize::MAX).into())
+            .any(|v| *v == (usize::MAX).into())
         {
             return Err(anyhow!("holes in points of interest"));
         }
@@ -207,7 +161,7 @@ pub fn parse(s: &str) -> IResult<&str, Map> {
 #[test]
 fn day24() -> Result<()> {
     use std::string::ToString;
-    const EXAMPLE: &'static str = "\
+    const EXAMPLE: &str = "\
 ###########
 #0.1.....2#
 #.#######.#
@@ -1,48 +1,25 @@
 use crate::assembunny::*;
 use crate::prelude::*;
 
-pub fn pt(input: Vec<Instruction>) -> Result<i64> {
-    let mut prog = Program::new(input)?;
-    let mut start_idx = -1i64;
-    let mut state: HashSet<(i64, Registers, bool)> = HashSet::new();
-    'outer: loop {
-        prog.instruction_ptr = 0;
-        start_idx += 1;
-        let mut expect_high = false;
-        let mut regs = Registers::default();
-        regs[0] = start_idx;
+// Outputs, or steps without an output, after which a signal that has not
+// repeated is rejected
+const LIMITS: OutputLimits = OutputLimits {
+    outputs: 1024,
+    steps: 1_000_000,
+};
 
-        state.clear();
-        state.insert((prog.instruction_ptr, regs, expect_high));
-        let mut has_false_output = false;
-        while prog.run_one(&mut regs, |value| {
-            let is_correct = match value {
-                0 => !expect_high,
-                1 => expect_high,
-                _ => false,
-            };
-            if !is_correct {
-                has_false_output = true;
-            }
-            expect_high = !expect_high;
-        }) {
-            if has_false_output {
-                continue 'outer;
-            }
-            if !state.insert((prog.instruction_ptr, regs, expect_high)) {
-                break 'outer Ok(start_idx);
-            }
-        }
-    }
+#[aoc(day = 25, part = 1, parser = parse, params(backend = "compiled", limit = 1000000))]
+pub fn pt(input: Vec<Instruction>, backend: Backend, limit: i64) -> Result<i64> {
+    let prog = Program::new(input)?;
+    prog.find_input(backend, &Registers::default(), 0, 0..limit, &[0, 1], LIMITS)
 }
 
 pub fn parse(s: &str) -> IResult<&str, Vec<Instruction>> {
     use parsers::*;
     map_res(parse_assembunny, |instrs| {
         for instr in &instrs {
-            match instr {
-                Instruction::Toggle(_) => return Err(()),
-                _ => {}
+            if let Instruction::Toggle(_) = instr {
+                return Err(());
             }
         }
         Ok(instrs)
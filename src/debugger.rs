@@ -0,0 +1,717 @@
+use advent_of_code_2016::assembunny::{Instruction, Program};
+use anyhow::{anyhow, Context, Result};
+use std::fmt::{self, Display};
+use std::io::{BufRead, Write};
+
+pub const HELP: &str = "\
+Commands (an empty line repeats the previous command):
+  s, step [n]           execute n instructions (default: 1)
+  c, continue           run until a breakpoint, a watched register changes or
+                        the program halts, any key interrupts
+  b, break <n>          break before executing instruction n
+  b, break <condition>  break once a condition becomes true, e.g. a >= 100
+  d, delete <n>         delete breakpoint n, as numbered by info
+  w, watch <register>   break whenever a register changes
+  unwatch <register>    stop watching a register
+  set <register> <n>    change the value of a register
+  in <n>...             queue values for in instructions to read
+  i, info               list breakpoints and watched registers
+  l, list               print the whole program
+  r, reset              restore the program and registers to their initial state
+  q, quit               exit the debugger
+  h, help               print this message";
+
+// Number of instructions shown before and after the current one
+const CONTEXT_LINES: usize = 4;
+
+// Number of steps between checks for a key press while continuing
+const POLL_INTERVAL: u64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Instruction(usize),
+    Condition {
+        register: usize,
+        comparison: Comparison,
+        value: i64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Steps,
+    Breakpoint(usize),
+    Watch { register: usize, old: i64, new: i64 },
+    Halted,
+    WaitingForInput,
+    Interrupted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugCommand {
+    Step(u64),
+    Continue,
+    Break(Breakpoint),
+    Delete(usize),
+    Watch(usize),
+    Unwatch(usize),
+    Set(usize, i64),
+    In(Vec<i64>),
+    Info,
+    List,
+    Reset,
+    Quit,
+    Help,
+}
+
+/// An assembunny program being stepped through one instruction at a time.
+pub struct Debugger {
+    initial: Program,
+    initial_registers: Vec<i64>,
+    program: Program,
+    registers: Vec<i64>,
+    steps: u64,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<usize>,
+    // Instruction each toggled instruction had when the program was loaded
+    toggled: Vec<Option<Instruction>>,
+    last_toggled: Option<usize>,
+    // Registers changed by the most recent step or continue
+    changed: Vec<bool>,
+    output: Vec<i64>,
+}
+
+fn register_name(register: usize) -> char {
+    (b'a' + register as u8) as char
+}
+
+fn parse_register(s: &str) -> Result<usize> {
+    match s.as_bytes() {
+        &[c] if c.is_ascii_lowercase() => Ok((c - b'a') as usize),
+        _ => Err(anyhow!("expected a register, got {:?}", s)),
+    }
+}
+
+impl Comparison {
+    fn holds(self, lhs: i64, rhs: i64) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+impl Breakpoint {
+    fn condition_holds(&self, registers: &[i64]) -> bool {
+        match *self {
+            Breakpoint::Instruction(_) => false,
+            Breakpoint::Condition {
+                register,
+                comparison,
+                value,
+            } => comparison.holds(registers[register], value),
+        }
+    }
+}
+
+impl std::str::FromStr for Breakpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if let Ok(index) = s.parse() {
+            return Ok(Breakpoint::Instruction(index));
+        }
+        let op_start = s.find(|c| "=!<>".contains(c)).ok_or_else(|| {
+            anyhow!("expected an instruction index or a condition such as a >= 100")
+        })?;
+        let op_end = s[op_start..]
+            .find(|c| !"=!<>".contains(c))
+            .map_or(s.len(), |len| op_start + len);
+        let comparison = match &s[op_start..op_end] {
+            "==" | "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            op => return Err(anyhow!("unknown comparison {:?}", op)),
+        };
+        Ok(Breakpoint::Condition {
+            register: parse_register(&s[..op_start])?,
+            comparison,
+            value: s[op_end..]
+                .parse()
+                .with_context(|| format!("expected a number, got {:?}", &s[op_end..]))?,
+        })
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Breakpoint::Instruction(index) => write!(f, "instruction {}", index),
+            Breakpoint::Condition {
+                register,
+                comparison,
+                value,
+            } => {
+                let op = match comparison {
+                    Comparison::Equal => "==",
+                    Comparison::NotEqual => "!=",
+                    Comparison::Less => "<",
+                    Comparison::LessOrEqual => "<=",
+                    Comparison::Greater => ">",
+                    Comparison::GreaterOrEqual => ">=",
+                };
+                write!(f, "{} {} {}", register_name(register), op, value)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for DebugCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (command, args) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let args = args.trim();
+        let no_args = |command: DebugCommand| {
+            if args.is_empty() {
+                Ok(command)
+            } else {
+                Err(anyhow!("unexpected arguments {:?}", args))
+            }
+        };
+        match command {
+            "s" | "step" if args.is_empty() => Ok(DebugCommand::Step(1)),
+            "s" | "step" => match args.parse() {
+                Ok(0) | Err(_) => Err(anyhow!("step expects a positive number")),
+                Ok(n) => Ok(DebugCommand::Step(n)),
+            },
+            "c" | "continue" => no_args(DebugCommand::Continue),
+            "b" | "break" => Ok(DebugCommand::Break(args.parse()?)),
+            "d" | "delete" => {
+                Ok(DebugCommand::Delete(args.parse().map_err(|_| {
+                    anyhow!("delete expects a breakpoint number")
+                })?))
+            }
+            "w" | "watch" => Ok(DebugCommand::Watch(parse_register(args)?)),
+            "unwatch" => Ok(DebugCommand::Unwatch(parse_register(args)?)),
+            "set" => {
+                let (register, value) = args
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| anyhow!("set expects a register and a value"))?;
+                Ok(DebugCommand::Set(
+                    parse_register(register)?,
+                    value
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("expected a number, got {:?}", value.trim()))?,
+                ))
+            }
+            "in" => Ok(DebugCommand::In(
+                args.split_whitespace()
+                    .map(|value| {
+                        value
+                            .parse()
+                            .map_err(|_| anyhow!("expected a number, got {:?}", value))
+                    })
+                    .collect::<Result<_>>()?,
+            )),
+            "i" | "info" => no_args(DebugCommand::Info),
+            "l" | "list" => no_args(DebugCommand::List),
+            "r" | "reset" => no_args(DebugCommand::Reset),
+            "q" | "quit" => no_args(DebugCommand::Quit),
+            "h" | "help" => no_args(DebugCommand::Help),
+            _ => Err(anyhow!("unknown command {:?}, try help", command)),
+        }
+    }
+}
+
+impl Debugger {
+    pub fn new(program: Program, registers: Vec<i64>) -> Self {
+        // Fused loops would skip over breakpoints inside of them
+        let program = program.without_optimizations();
+        Debugger {
+            toggled: vec![None; program.instructions().len()],
+            initial: program.clone(),
+            changed: vec![false; registers.len()],
+            initial_registers: registers.clone(),
+            program,
+            registers,
+            steps: 0,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            last_toggled: None,
+            output: Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.program = self.initial.clone();
+        self.registers.copy_from_slice(&self.initial_registers);
+        self.steps = 0;
+        self.toggled.iter_mut().for_each(|toggled| *toggled = None);
+        self.last_toggled = None;
+        self.changed.iter_mut().for_each(|changed| *changed = false);
+        self.output.clear();
+    }
+
+    fn is_halted(&self) -> bool {
+        self.program.instruction_ptr < 0
+            || self.program.instruction_ptr >= self.program.instructions().len() as i64
+    }
+
+    // Executes a single instruction, returns false if the program has halted
+    fn step(&mut self) -> bool {
+        let ptr = self.program.instruction_ptr;
+        let toggle_target = match self.program.instructions().get(ptr as usize) {
+            Some(Instruction::Toggle(register)) if ptr >= 0 => {
+                let target = ptr + self.registers[*register];
+                if target >= 0 && target < self.program.instructions().len() as i64 {
+                    Some(target as usize)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        let before = toggle_target.map(|target| self.program.instructions()[target]);
+
+        let output = &mut self.output;
+        if !self
+            .program
+            .run_one(&mut self.registers, |value| output.push(value))
+        {
+            return false;
+        }
+        self.steps += 1;
+
+        if let (Some(target), Some(before)) = (toggle_target, before) {
+            let original = &mut self.toggled[target];
+            *original = match *original {
+                // Toggled back into its original form
+                Some(original) if original == self.program.instructions()[target] => None,
+                Some(original) => Some(original),
+                None => Some(before),
+            };
+            self.last_toggled = Some(target);
+        }
+        true
+    }
+
+    /// Executes instructions until a stop condition is met, or until `limit`
+    /// instructions ran. `interrupted` is polled periodically.
+    pub fn run<F>(&mut self, limit: Option<u64>, mut interrupted: F) -> Stop
+    where
+        F: FnMut() -> bool,
+    {
+        let start_registers = self.registers.clone();
+        let mut before = self.registers.clone();
+        let mut executed = 0;
+        let stop = loop {
+            if limit == Some(executed) {
+                break Stop::Steps;
+            }
+            if executed % POLL_INTERVAL == POLL_INTERVAL - 1 && interrupted() {
+                break Stop::Interrupted;
+            }
+
+            before.copy_from_slice(&self.registers);
+            if !self.step() {
+                break if self.program.is_waiting_for_input() {
+                    Stop::WaitingForInput
+                } else {
+                    Stop::Halted
+                };
+            }
+            executed += 1;
+
+            if let Some(&register) = self
+                .watches
+                .iter()
+                .find(|&&register| before[register] != self.registers[register])
+            {
+                break Stop::Watch {
+                    register,
+                    old: before[register],
+                    new: self.registers[register],
+                };
+            }
+            // Conditions only trigger when they become true, so that
+            // continuing past them is possible
+            if let Some(index) = self
+                .breakpoints
+                .iter()
+                .position(|breakpoint| match *breakpoint {
+                    Breakpoint::Instruction(index) => self.program.instruction_ptr == index as i64,
+                    condition => {
+                        condition.condition_holds(&self.registers)
+                            && !condition.condition_holds(&before)
+                    }
+                })
+            {
+                break Stop::Breakpoint(index);
+            }
+            if self.is_halted() {
+                break Stop::Halted;
+            }
+        };
+        for (changed, (old, new)) in self
+            .changed
+            .iter_mut()
+            .zip(start_registers.iter().zip(&self.registers))
+        {
+            *changed = old != new;
+        }
+        stop
+    }
+
+    /// Prints the instructions surrounding the current one, followed by the
+    /// registers and the output so far.
+    pub fn show<W: Write>(&self, out: &mut W, full: bool) -> crossterm::Result<()> {
+        use crossterm::{
+            queue,
+            style::{style, Print, PrintStyledContent, Stylize},
+        };
+
+        let instructions = self.program.instructions();
+        let ptr = self.program.instruction_ptr;
+        let range = if full || ptr < 0 {
+            0..instructions.len()
+        } else {
+            let ptr = ptr as usize;
+            ptr.saturating_sub(CONTEXT_LINES)..(ptr + CONTEXT_LINES + 1).min(instructions.len())
+        };
+        let width = instructions.len().saturating_sub(1).to_string().len();
+        for idx in range {
+            let is_current = idx as i64 == ptr;
+            let has_breakpoint = self.breakpoints.contains(&Breakpoint::Instruction(idx));
+            queue!(
+                out,
+                PrintStyledContent(if has_breakpoint {
+                    "*".red()
+                } else {
+                    style(" ")
+                }),
+                PrintStyledContent(if is_current {
+                    ">".green().bold()
+                } else {
+                    style(" ")
+                }),
+                PrintStyledContent(style(format!(" {:>width$}  ", idx, width = width)).grey())
+            )?;
+            let text = instructions[idx].to_string();
+            let text = match self.toggled[idx] {
+                Some(_) if self.last_toggled == Some(idx) => text.yellow().bold(),
+                Some(_) => text.yellow(),
+                None if is_current => text.white().bold(),
+                None => style(text),
+            };
+            queue!(out, PrintStyledContent(text))?;
+            if let Some(original) = &self.toggled[idx] {
+                queue!(
+                    out,
+                    PrintStyledContent(style(format!("  ; toggled, was {}", original)).grey())
+                )?;
+            }
+            queue!(out, Print('\n'))?;
+        }
+
+        queue!(
+            out,
+            PrintStyledContent(style(format!("step {}:", self.steps)).grey())
+        )?;
+        for (register, &value) in self.registers.iter().enumerate() {
+            let text = format!(" {}={}", register_name(register), value);
+            let text = if self.changed[register] {
+                text.yellow().bold()
+            } else if self.watches.contains(&register) {
+                text.blue()
+            } else {
+                style(text)
+            };
+            queue!(out, PrintStyledContent(text))?;
+        }
+        if !self.output.is_empty() {
+            // Only the end of long outputs such as day 25's clock signal
+            let skip = self.output.len().saturating_sub(32);
+            let output = self.output[skip..]
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>();
+            queue!(
+                out,
+                PrintStyledContent(
+                    style(format!(
+                        "\nout{}: {}",
+                        if skip > 0 { " (last 32)" } else { "" },
+                        output.join(",")
+                    ))
+                    .grey()
+                )
+            )?;
+        }
+        queue!(out, Print('\n'))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn print_info<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        if self.breakpoints.is_empty() && self.watches.is_empty() {
+            return writeln!(out, "no breakpoints or watched registers");
+        }
+        for (idx, breakpoint) in self.breakpoints.iter().enumerate() {
+            writeln!(out, "breakpoint {}: {}", idx, breakpoint)?;
+        }
+        for &register in &self.watches {
+            writeln!(out, "watching {}", register_name(register))?;
+        }
+        Ok(())
+    }
+
+    fn check_register(&self, register: usize) -> Result<()> {
+        if register < self.registers.len() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "register {} is out of range, the program has {} registers",
+                register_name(register),
+                self.registers.len()
+            ))
+        }
+    }
+
+    fn describe_stop(&self, stop: Stop) -> String {
+        match stop {
+            Stop::Steps => String::new(),
+            Stop::Breakpoint(idx) => format!("hit breakpoint {}: {}", idx, self.breakpoints[idx]),
+            Stop::Watch { register, old, new } => {
+                format!(
+                    "{} changed from {} to {}",
+                    register_name(register),
+                    old,
+                    new
+                )
+            }
+            Stop::Halted => "program halted".to_owned(),
+            Stop::WaitingForInput => "waiting for input, queue values with in".to_owned(),
+            Stop::Interrupted => "interrupted".to_owned(),
+        }
+    }
+
+    /// Executes a command, returns false once the debugger should exit.
+    pub fn execute<W: Write>(&mut self, out: &mut W, command: DebugCommand) -> Result<bool> {
+        match command {
+            DebugCommand::Step(n) => {
+                let stop = self.run(Some(n), || false);
+                self.show(out, false)?;
+                if stop != Stop::Steps {
+                    writeln!(out, "{}", self.describe_stop(stop))?;
+                }
+            }
+            DebugCommand::Continue => {
+                // Raw mode lets continue notice key presses, failing to enable
+                // it only means that continue cannot be interrupted. It has to
+                // be off again before printing, as it leaves '\n' untranslated
+                let raw_mode = crossterm::terminal::enable_raw_mode().is_ok();
+                let stop = self.run(None, poll_key_press);
+                if raw_mode {
+                    crossterm::terminal::disable_raw_mode()?;
+                }
+                self.show(out, false)?;
+                writeln!(out, "{}", self.describe_stop(stop))?;
+            }
+            DebugCommand::Break(breakpoint) => {
+                match breakpoint {
+                    Breakpoint::Instruction(index)
+                        if index >= self.program.instructions().len() =>
+                    {
+                        return Err(anyhow!(
+                            "the program only has {} instructions",
+                            self.program.instructions().len()
+                        ));
+                    }
+                    Breakpoint::Condition { register, .. } => self.check_register(register)?,
+                    _ => {}
+                }
+                self.breakpoints.push(breakpoint);
+                writeln!(
+                    out,
+                    "breakpoint {}: {}",
+                    self.breakpoints.len() - 1,
+                    breakpoint
+                )?;
+            }
+            DebugCommand::Delete(idx) => {
+                if idx >= self.breakpoints.len() {
+                    return Err(anyhow!("there is no breakpoint {}", idx));
+                }
+                self.breakpoints.remove(idx);
+            }
+            DebugCommand::Watch(register) => {
+                self.check_register(register)?;
+                if !self.watches.contains(&register) {
+                    self.watches.push(register);
+                }
+            }
+            DebugCommand::Unwatch(register) => self.watches.retain(|&r| r != register),
+            DebugCommand::Set(register, value) => {
+                self.check_register(register)?;
+                self.registers[register] = value;
+                self.show(out, false)?;
+            }
+            DebugCommand::In(values) => values
+                .into_iter()
+                .for_each(|value| self.program.push_input(value)),
+            DebugCommand::Info => self.print_info(out)?,
+            DebugCommand::List => self.show(out, true)?,
+            DebugCommand::Reset => {
+                self.reset();
+                self.show(out, false)?;
+            }
+            DebugCommand::Quit => return Ok(false),
+            DebugCommand::Help => writeln!(out, "{}", HELP)?,
+        }
+        Ok(true)
+    }
+}
+
+// Checks for a key press without blocking, only possible in raw mode
+fn poll_key_press() -> bool {
+    use crossterm::event::{poll, read, Event};
+    match poll(std::time::Duration::from_secs(0)) {
+        Ok(true) => matches!(read(), Ok(Event::Key(_))),
+        _ => false,
+    }
+}
+
+/// Runs the interactive prompt on standard input.
+pub fn run(program: Program, registers: Vec<i64>) -> Result<()> {
+    let mut debugger = Debugger::new(program, registers);
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "type help for a list of commands")?;
+    debugger.show(&mut out, false)?;
+
+    let stdin = std::io::stdin();
+    let mut previous: Option<DebugCommand> = None;
+    loop {
+        write!(out, "(debug) ")?;
+        out.flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let command = match (line.trim().is_empty(), &previous) {
+            (true, Some(previous)) => previous.clone(),
+            (true, None) => continue,
+            (false, _) => match line.parse::<DebugCommand>() {
+                Ok(command) => command,
+                Err(err) => {
+                    writeln!(out, "error: {:#}", err)?;
+                    continue;
+                }
+            },
+        };
+
+        match debugger.execute(&mut out, command.clone()) {
+            Ok(true) => previous = Some(command),
+            Ok(false) => return Ok(()),
+            Err(err) => writeln!(out, "error: {:#}", err)?,
+        }
+    }
+}
+
+#[test]
+fn debugger() -> Result<()> {
+    use advent_of_code_2016::assembunny::{assemble, Dialect};
+
+    let source = "\
+cpy 2 a
+tgl a
+cpy 1 b
+tgl a
+dec a
+dec a
+dec a";
+    let program = Program::new(assemble(source, Dialect::PUZZLE)?)?;
+    let mut debugger = Debugger::new(program, vec![0; 4]);
+    let mut out = Vec::new();
+
+    debugger.execute(&mut out, "b 3".parse()?)?;
+    debugger.execute(&mut out, "break a <= 0".parse()?)?;
+    assert_eq!(
+        debugger.breakpoints[1],
+        Breakpoint::Condition {
+            register: 0,
+            comparison: Comparison::LessOrEqual,
+            value: 0
+        }
+    );
+
+    // The first tgl turns the second one into an inc
+    assert_eq!(debugger.run(None, || false), Stop::Breakpoint(0));
+    assert_eq!(debugger.program.instruction_ptr, 3);
+    assert_eq!(debugger.toggled[3], Some(Instruction::Toggle(0)));
+    assert_eq!(debugger.last_toggled, Some(3));
+    assert_eq!(debugger.changed, [true, true, false, false]);
+
+    debugger.execute(&mut out, "w a".parse()?)?;
+    assert_eq!(
+        debugger.run(None, || false),
+        Stop::Watch {
+            register: 0,
+            old: 2,
+            new: 3
+        }
+    );
+    debugger.execute(&mut out, "unwatch a".parse()?)?;
+    assert_eq!(debugger.run(None, || false), Stop::Breakpoint(1));
+    assert_eq!(debugger.registers[0], 0);
+    assert_eq!(debugger.run(Some(1), || false), Stop::Halted);
+
+    debugger.execute(&mut out, DebugCommand::Reset)?;
+    debugger.execute(&mut out, "set b 7".parse()?)?;
+    assert_eq!(debugger.registers, [0, 7, 0, 0]);
+    assert_eq!(debugger.toggled[3], None);
+    assert_eq!(debugger.run(Some(2), || false), Stop::Steps);
+
+    assert_eq!("step 5".parse::<DebugCommand>()?, DebugCommand::Step(5));
+    assert!("step 0".parse::<DebugCommand>().is_err());
+    assert!("break a => 1".parse::<DebugCommand>().is_err());
+    assert!("set a".parse::<DebugCommand>().is_err());
+    assert!(debugger.execute(&mut out, "b 7".parse()?).is_err());
+    assert!(debugger.execute(&mut out, "w e".parse()?).is_err());
+    assert!(debugger.execute(&mut out, "break e > 1".parse()?).is_err());
+
+    let program = Program::with_dialect(
+        assemble("in a\nin b", Dialect::EXTENDED)?,
+        Dialect::EXTENDED,
+    )?;
+    let mut debugger = Debugger::new(program, vec![0; 4]);
+    debugger.execute(&mut out, "in 5".parse()?)?;
+    assert_eq!(debugger.run(None, || false), Stop::WaitingForInput);
+    debugger.execute(&mut out, "in 6".parse()?)?;
+    assert_eq!(debugger.run(None, || false), Stop::Halted);
+    assert_eq!(debugger.registers, [5, 6, 0, 0]);
+
+    Ok(())
+}
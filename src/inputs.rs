@@ -0,0 +1,157 @@
+use crate::cli::InputSource;
+use crate::registry::ParamOverride;
+use std::path::Path;
+
+/// Name of the input read from `./data/dayXX.txt`, `--input` or `--stdin`.
+pub const DEFAULT_INPUT: &str = "default";
+
+/// A puzzle input, or the reason it could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub name: String,
+    pub contents: Result<String, String>,
+    /// Parameter overrides read from the `.params` file next to the input.
+    pub params: Vec<ParamOverride>,
+}
+
+fn read_params(path: &Path) -> Result<Vec<ParamOverride>, String> {
+    let path = path.with_extension("params");
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => ParamOverride::parse_file(&contents)
+            .map_err(|err| format!("in {}: {:#}", path.display(), err)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(format!("cannot read {}: {}", path.display(), err)),
+    }
+}
+
+fn read_file(name: String, path: &Path) -> Input {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| format!("cannot read input file {}: {}", path.display(), err));
+    match (contents, read_params(path)) {
+        (Ok(contents), Ok(params)) => Input {
+            name,
+            contents: Ok(contents),
+            params,
+        },
+        (Err(err), _) | (_, Err(err)) => Input {
+            name,
+            contents: Err(err),
+            params: Vec::new(),
+        },
+    }
+}
+
+// Names end up in the answers file, which separates its fields by spaces,
+// and the default input is stored without a name
+fn invalid_name(name: &str) -> Option<&'static str> {
+    if name == DEFAULT_INPUT {
+        Some("the name is reserved for dayXX.txt")
+    } else if name.is_empty() || name.contains(char::is_whitespace) {
+        Some("input names cannot be empty or contain whitespace")
+    } else {
+        None
+    }
+}
+
+/// Finds all inputs of a day.
+///
+/// Besides `<data_dir>/dayXX.txt` every `.txt` file in `<data_dir>/dayXX/` is an
+/// input of its own, named after the file, so that solutions can be validated
+/// against the puzzle inputs of several accounts. Files named `default` or
+/// with whitespace in their name are reported as errors. An input may come
+/// with a `.params` file of the same name, overriding the parameters of its
+/// parts.
+pub fn collect(
+    source: &InputSource,
+    data_dir: &Path,
+    module_name: &str,
+    stdin: Option<&str>,
+) -> Vec<Input> {
+    match source {
+        InputSource::File(path) => vec![read_file(DEFAULT_INPUT.to_owned(), path)],
+        InputSource::Stdin => vec![Input {
+            name: DEFAULT_INPUT.to_owned(),
+            contents: Ok(stdin.unwrap_or_default().to_owned()),
+            params: Vec::new(),
+        }],
+        InputSource::DataDirectory => {
+            let default_path = data_dir.join(format!("{}.txt", module_name));
+            let mut inputs = Vec::new();
+            if default_path.exists() {
+                inputs.push(read_file(DEFAULT_INPUT.to_owned(), &default_path));
+            }
+
+            let mut named_paths = std::fs::read_dir(data_dir.join(module_name))
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+                .collect::<Vec<_>>();
+            named_paths.sort();
+            for path in named_paths {
+                let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+                match invalid_name(&name) {
+                    Some(reason) => inputs.push(Input {
+                        contents: Err(format!("cannot use {}: {}", path.display(), reason)),
+                        name,
+                        params: Vec::new(),
+                    }),
+                    None => inputs.push(read_file(name, &path)),
+                }
+            }
+
+            // Report the conventional location as missing
+            if inputs.is_empty() {
+                inputs.push(read_file(DEFAULT_INPUT.to_owned(), &default_path));
+            }
+            inputs
+        }
+    }
+}
+
+#[test]
+fn inputs() -> anyhow::Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("aoc2016-inputs-{}", std::process::id()));
+    std::fs::create_dir_all(data_dir.join("day02"))?;
+    std::fs::write(data_dir.join("day01.txt"), "R2, L3")?;
+    std::fs::write(data_dir.join("day02").join("bob.txt"), "UUD")?;
+    std::fs::write(data_dir.join("day02").join("alice.txt"), "ULL")?;
+    std::fs::write(data_dir.join("day02").join("notes.md"), "ignored")?;
+    std::fs::create_dir_all(data_dir.join("day03"))?;
+    std::fs::write(data_dir.join("day03").join("default.txt"), "5 10 25")?;
+    std::fs::write(data_dir.join("day03").join("my input.txt"), "5 10 25")?;
+    std::fs::write(data_dir.join("day02").join("bob.params"), "pt2:size = 3\n")?;
+    std::fs::write(data_dir.join("day04.txt"), "")?;
+    std::fs::write(data_dir.join("day04.params"), "size\n")?;
+
+    let names_of = |module_name: &str| {
+        collect(&InputSource::DataDirectory, &data_dir, module_name, None)
+            .into_iter()
+            .map(|input| (input.name, input.contents.is_ok()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names_of("day01"), vec![("default".to_owned(), true)]);
+    assert_eq!(
+        names_of("day02"),
+        vec![("alice".to_owned(), true), ("bob".to_owned(), true)]
+    );
+    assert_eq!(
+        names_of("day03"),
+        vec![
+            ("default".to_owned(), false),
+            ("my input".to_owned(), false)
+        ]
+    );
+    assert_eq!(names_of("day05"), vec![("default".to_owned(), false)]);
+    assert_eq!(names_of("day04"), vec![("default".to_owned(), false)]);
+
+    let day02 = collect(&InputSource::DataDirectory, &data_dir, "day02", None);
+    assert!(day02[0].params.is_empty());
+    assert_eq!(day02[1].params, vec!["pt2:size=3".parse()?]);
+
+    let stdin = collect(&InputSource::Stdin, &data_dir, "day01", Some("R8"));
+    assert_eq!(stdin[0].contents, Ok("R8".to_owned()));
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Solutions to Advent of Code 2016, and the utilities they are built on:
+//! path finding in [`astar`], routes through all points in [`tsp`], grids in
+//! [`mat2`] and [`vec2`], the assembunny interpreter in [`assembunny`] with
+//! its static analysis in [`symbolic`], and `nom` helpers in [`parsers`].
+
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+
+pub mod assembunny;
+pub mod astar;
+pub mod cancel;
+pub mod mat2;
+pub mod parse_error;
+pub mod parsers;
+pub mod prelude;
+pub mod registry;
+pub mod symbolic;
+#[macro_use]
+mod test_helpers;
+pub mod tsp;
+pub mod vec2;
+pub mod vec3;
+
+mod day01;
+mod day02;
+mod day03;
+mod day04;
+mod day05;
+mod day06;
+mod day07;
+mod day08;
+mod day09;
+mod day10;
+mod day11;
+mod day12;
+mod day13;
+mod day14;
+mod day15;
+mod day16;
+mod day17;
+mod day18;
+mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod day25;
+
+use anyhow::{anyhow, Result};
+
+/// Solves part 1 or 2 of a day with the default parameters, returning the
+/// answer as it would be printed by the runner, without terminal styling.
+pub fn solve(day: u32, part: u32, input: &str) -> Result<String> {
+    let solver = inventory::iter::<registry::Part>
+        .into_iter()
+        .find(|registered| registered.day == day && registered.parts.contains(&part))
+        .ok_or_else(|| anyhow!("no solution registered for day {} part {}", day, part))?;
+    match (solver.solver)(input, &registry::Params::default())? {
+        registry::Answer::Single(answer) => Ok(answer),
+        registry::Answer::Both(pt1, pt2) => Ok(if part == 1 { pt1 } else { pt2 }),
+    }
+}
+
+#[test]
+fn solve_api() -> Result<()> {
+    assert_eq!(solve(1, 2, "R8, R4, R4, R8")?, "4");
+    assert_eq!(solve(8, 1, "rect 3x2\nrotate column x=1 by 1")?, "6");
+    assert!(solve(8, 2, "rect 3x2")?.starts_with("███"));
+    assert!(solve(26, 1, "").is_err());
+
+    Ok(())
+}
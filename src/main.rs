@@ -1,58 +1,28 @@
-#![feature(
-    auto_traits,
-    iter_intersperse,
-    negative_impls,
-    stmt_expr_attributes,
-    trait_alias
-)]
-#![allow(incomplete_features)]
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
 
-mod assembunny;
-mod astar;
-mod mat2;
-mod parsers;
-mod prelude;
-#[macro_use]
-mod test_helpers;
-mod vec2;
-mod vec3;
-
+mod answers;
+mod cli;
+mod debugger;
+mod inputs;
+mod profiler;
+mod report;
+
+use advent_of_code_2016::assembunny::{self, Program};
+use advent_of_code_2016::cancel::{self, CancellationToken};
+use advent_of_code_2016::prelude::IterEx;
+use advent_of_code_2016::registry::{self, Params, Solver};
+use advent_of_code_2016::symbolic;
+use answers::{Answers, Verdict};
 use anyhow::anyhow;
-use aoc_proc_macro::generate_module_list;
-use prelude::IterEx;
+use cli::{Command, InputSource, Options};
+use itertools::Itertools;
+use report::Format;
 use std::fmt::{self, Display};
 use std::io::Write;
+use std::path::Path;
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-
-generate_module_list!(DAY_LIST;
-    day01[pt1, pt2]: parse,
-    day02[pt1, pt2]: parse,
-    day03[pt1, pt2]: parse,
-    day04[pt1, pt2]: parse,
-    day05[pt1, pt2],
-    day06[pt1, pt2]: parse,
-    day07[pt1, pt2]: parse,
-    day08[pts]: parse,
-    day09[pt1, pt2],
-    day10[pts]: parse,
-    day11[pt1, pt2]: parse,
-    day12[pt1, pt2]: parse,
-    day13[pt1, pt2]: parse,
-    day14[pt1, pt2],
-    day15[pt1, pt2]: parse,
-    day16[pt1, pt2]: parse,
-    day17[pt1, pt2]: parse,
-    day18[pt1, pt2]: parse,
-    day19[pt1, pt2]: parse,
-    day20[pt1, pt2]: parse,
-    day21[pt1, pt2]: parse,
-    day22[pt1, pt2]: parse,
-    day23[pt1, pt2]: parse,
-    day24[pts]: parse,
-    day25[pt]: parse,
-);
+use std::time::{Duration, Instant};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum TaskState {
@@ -73,24 +43,95 @@ impl Display for RepeatChar {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FormatDuration(Duration);
+impl Display for FormatDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0.as_secs_f64();
+        if secs >= 1.0 {
+            write!(f, "{:.3}s", secs)
+        } else if secs >= 1e-3 {
+            write!(f, "{:.2}ms", secs * 1e3)
+        } else {
+            write!(f, "{:.1}µs", secs * 1e6)
+        }
+    }
+}
+
 // Used on main thread
 struct TaskTracker {
     module_name: &'static str,
     part_name: &'static str,
+    input_name: String,
     state: TaskState,
     output: Option<Result<String, anyhow::Error>>,
+    // Wall-clock time of every run, only empty if the task never ran
+    timings: Vec<Duration>,
+    // Comparison against the answers file, if answers are being checked
+    verdict: Option<Verdict>,
+    // Whether --param changed a parameter, in which case the recorded answer
+    // no longer applies
+    params_overridden: bool,
+    // When a worker thread picked up the task, used to enforce the timeout
+    started: Option<Instant>,
+    cancel: CancellationToken,
 }
 
 // Used on worker thread
 struct TaskWork {
     input: String,
-    function: fn(&str) -> Result<String, anyhow::Error>,
+    params: Params,
+    function: Solver,
+    cancel: CancellationToken,
 }
 
+type WorkQueue = Arc<Mutex<Vec<Option<TaskWork>>>>;
+
 // Sent from worker thread to main thread
 enum TaskUpdate {
     WorkStarted(usize),
-    WorkDone(usize, Result<String, anyhow::Error>),
+    WorkDone(usize, Result<String, anyhow::Error>, Vec<Duration>),
+}
+
+// Runs the work `runs` times, returning the output of the last run together
+// with the time each run took. Stops early if a run produces an error.
+fn run_work(work: TaskWork, runs: usize) -> (Result<String, anyhow::Error>, Vec<Duration>) {
+    let mut timings = Vec::with_capacity(runs);
+    let mut result = Err(anyhow!("task did not run"));
+    cancel::set_current(Some(work.cancel.clone()));
+    for _ in 0..runs {
+        let start = Instant::now();
+        result = (work.function)(&work.input, &work.params).map(|answer| answer.to_string());
+        timings.push(start.elapsed());
+        if result.is_err() {
+            break;
+        }
+    }
+    cancel::set_current(None);
+    (result, timings)
+}
+
+fn spawn_worker(
+    thread_idx: usize,
+    work_queue: WorkQueue,
+    result_sender: mpsc::Sender<TaskUpdate>,
+    runs: usize,
+) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+        .name(format!("worker thread {}", thread_idx))
+        .spawn(move || {
+            while let Some((task_index, work)) = pop_work(&work_queue) {
+                result_sender
+                    .send(TaskUpdate::WorkStarted(task_index))
+                    .unwrap();
+                let (result, timings) = std::panic::catch_unwind(move || run_work(work, runs))
+                    .unwrap_or_else(|_| (Err(anyhow!("task panicked")), Vec::new()));
+                result_sender
+                    .send(TaskUpdate::WorkDone(task_index, result, timings))
+                    .unwrap();
+            }
+        })
+        .unwrap()
 }
 
 fn pop_work(work_queue: &Mutex<Vec<Option<TaskWork>>>) -> Option<(usize, TaskWork)> {
@@ -125,7 +166,7 @@ fn init_progress<W: Write>(out: &mut W) -> crossterm::Result<()> {
     Ok(())
 }
 
-fn update_progress<W: Write>(out: &mut W, tasks: &Vec<TaskTracker>) -> crossterm::Result<()> {
+fn update_progress<W: Write>(out: &mut W, tasks: &[TaskTracker]) -> crossterm::Result<()> {
     use arrayvec::ArrayVec;
     use crossterm::{
         cursor::MoveUp,
@@ -151,27 +192,30 @@ fn update_progress<W: Write>(out: &mut W, tasks: &Vec<TaskTracker>) -> crossterm
         Print("] ")
     )?;
 
-    for sections in tasks
+    let running = tasks
         .iter()
         .filter(|task| task.state == TaskState::Running)
         .map(|task| {
-            let mut sections = ArrayVec::<StyledContent<&'static str>, 3>::new();
+            let mut sections = ArrayVec::<StyledContent<&str>, 5>::new();
             sections.push(task.module_name.green());
             sections.push(style(" "));
             sections.push(task.part_name.blue().bold());
+            if task.input_name != inputs::DEFAULT_INPUT {
+                sections.push(style(" "));
+                sections.push(task.input_name.as_str().grey());
+            }
             sections
-        })
-        .intersperse({
-            let mut section = ArrayVec::new();
-            section.push(", ".grey());
-            section
-        })
-        .limit(10, || {
-            let mut section = ArrayVec::new();
-            section.push("...".grey());
-            section
-        })
-    {
+        });
+    let separator = {
+        let mut section = ArrayVec::new();
+        section.push(", ".grey());
+        section
+    };
+    for sections in Itertools::intersperse(running, separator).limit(10, || {
+        let mut section = ArrayVec::new();
+        section.push("...".grey());
+        section
+    }) {
         for section in sections {
             queue!(out, PrintStyledContent(section))?;
         }
@@ -182,7 +226,60 @@ fn update_progress<W: Write>(out: &mut W, tasks: &Vec<TaskTracker>) -> crossterm
     Ok(())
 }
 
-fn output_results<W: Write>(out: &mut W, tasks: &Vec<TaskTracker>) -> crossterm::Result<()> {
+fn output_timings<W: Write>(out: &mut W, timings: &[Duration]) -> crossterm::Result<()> {
+    use crossterm::{
+        queue,
+        style::{style, PrintStyledContent, Stylize},
+    };
+
+    match report::timing_stats(timings) {
+        None => {}
+        Some((min, _, _)) if timings.len() == 1 => queue!(
+            out,
+            PrintStyledContent(style(format!(" ({})", FormatDuration(min))).grey())
+        )?,
+        Some((min, median, max)) => queue!(
+            out,
+            PrintStyledContent(
+                style(format!(
+                    " (min {}, median {}, max {} over {} runs)",
+                    FormatDuration(min),
+                    FormatDuration(median),
+                    FormatDuration(max),
+                    timings.len()
+                ))
+                .grey()
+            )
+        )?,
+    }
+    Ok(())
+}
+
+fn output_verdict<W: Write>(out: &mut W, verdict: Option<&Verdict>) -> crossterm::Result<()> {
+    use crossterm::{
+        queue,
+        style::{style, PrintStyledContent, Stylize},
+    };
+
+    match verdict {
+        None => {}
+        Some(Verdict::Pass) => queue!(out, PrintStyledContent(" pass".green()))?,
+        Some(Verdict::New) => queue!(out, PrintStyledContent(" new".yellow()))?,
+        Some(Verdict::Fail { expected }) if expected.contains('\n') => queue!(
+            out,
+            PrintStyledContent(" FAIL".red().bold()),
+            PrintStyledContent(style(format!(", expected:\n{}\n", expected)).red())
+        )?,
+        Some(Verdict::Fail { expected }) => queue!(
+            out,
+            PrintStyledContent(" FAIL".red().bold()),
+            PrintStyledContent(style(format!(" (expected {})", expected)).red())
+        )?,
+    }
+    Ok(())
+}
+
+fn output_results<W: Write>(out: &mut W, tasks: &[TaskTracker]) -> crossterm::Result<()> {
     use crossterm::{
         cursor::MoveUp,
         queue,
@@ -206,25 +303,34 @@ fn output_results<W: Write>(out: &mut W, tasks: &Vec<TaskTracker>) -> crossterm:
             Print(' '),
             PrintStyledContent(task.part_name.green().bold())
         )?;
+        if task.input_name != inputs::DEFAULT_INPUT {
+            queue!(
+                out,
+                PrintStyledContent(style(format!(" [{}]", task.input_name)).grey())
+            )?;
+        }
         if is_simple {
             queue!(
                 out,
                 Print(' '),
-                PrintStyledContent(style(output.unwrap()).white()),
-                Print('\n')
+                PrintStyledContent(style(output.unwrap()).white())
             )?;
+            output_timings(out, &task.timings)?;
+            output_verdict(out, task.verdict.as_ref())?;
+            queue!(out, Print('\n'))?;
             continue;
         }
         match output {
             Ok(value) => {
+                output_timings(out, &task.timings)?;
+                output_verdict(out, task.verdict.as_ref())?;
                 queue!(out, Print('\n'), PrintStyledContent(style(value).white()))?;
             }
             Err(err) => {
-                queue!(
-                    out,
-                    PrintStyledContent(" error\n".red().bold()),
-                    PrintStyledContent(style(err).red())
-                )?;
+                queue!(out, PrintStyledContent(" error".red().bold()))?;
+                output_timings(out, &task.timings)?;
+                output_verdict(out, task.verdict.as_ref())?;
+                queue!(out, Print('\n'), PrintStyledContent(style(err).red()))?;
             }
         }
 
@@ -236,6 +342,80 @@ fn output_results<W: Write>(out: &mut W, tasks: &Vec<TaskTracker>) -> crossterm:
     Ok(())
 }
 
+fn write_output(options: &Options, tasks: &[TaskTracker]) -> std::io::Result<()> {
+    let records = tasks.iter().map(|task| report::Record {
+        module_name: task.module_name,
+        part_name: task.part_name,
+        input_name: &task.input_name,
+        output: task.output.as_ref().unwrap(),
+        timings: &task.timings,
+        verdict: task.verdict.as_ref(),
+    });
+    match (options.format, &options.output) {
+        (Format::Text, _) => {
+            output_results(&mut std::io::stdout().lock(), tasks).map_err(std::io::Error::other)
+        }
+        (format, None) => report::write_records(&mut std::io::stdout().lock(), format, records),
+        (format, Some(path)) => report::write_records(
+            &mut std::io::BufWriter::new(std::fs::File::create(path)?),
+            format,
+            records,
+        ),
+    }
+}
+
+fn check_answers(options: &Options, tasks: &mut [TaskTracker]) -> anyhow::Result<()> {
+    // Answers belong to the inputs in the data directory, any other input
+    // would be compared to theirs
+    if options.input != InputSource::DataDirectory {
+        return Ok(());
+    }
+    let mut answers = Answers::load(&options.answers)?;
+    let mut recorded = 0;
+    for task in tasks.iter_mut().filter(|task| !task.params_overridden) {
+        let answer = match task.output.as_ref().unwrap() {
+            Ok(answer) => Some(report::strip_ansi(answer)),
+            Err(_) => None,
+        };
+        task.verdict = Some(answers.verdict(
+            task.module_name,
+            &task.input_name,
+            task.part_name,
+            answer.as_deref(),
+        ));
+        if let (true, Some(answer)) = (options.record, answer) {
+            answers.insert(task.module_name, &task.input_name, task.part_name, answer);
+            recorded += 1;
+        }
+    }
+    if options.record {
+        answers.save(&options.answers)?;
+        eprintln!(
+            "recorded {} answers to {}",
+            recorded,
+            options.answers.display()
+        );
+    }
+    Ok(())
+}
+
+// Assembles the program for --debug, --profile or --analyze, returning it with its
+// initial registers
+fn load_program(input: &str, options: &Options) -> anyhow::Result<(Program, Vec<i64>)> {
+    use anyhow::Context;
+    let instructions =
+        assembunny::assemble(input, options.dialect).context("cannot parse the program")?;
+    let mut program = Program::with_dialect(instructions, options.dialect)?;
+    for &value in &options.in_values {
+        program.push_input(value);
+    }
+    let mut registers = vec![0; options.dialect.registers];
+    for &(register, value) in &options.registers {
+        registers[register] = value;
+    }
+    Ok((program, registers))
+}
+
 fn main() {
     let default_panic_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
@@ -246,54 +426,189 @@ fn main() {
         let _ = stderr.flush();
     }));
 
-    let exclusive_day = std::env::args().skip(1).next();
-    let (mut task_trackers, task_work): (Vec<_>, Vec<_>) = DAY_LIST
-        .iter()
-        .cloned()
-        .filter(|&(module_name, _)| {
-            if let Some(exclusive_day) = &exclusive_day {
-                module_name.contains(exclusive_day)
-            } else {
-                true
+    let options = match Options::from_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {:#}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let day_list = registry::day_list();
+    match options.command {
+        Command::Run => {}
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Command::Debug | Command::Profile | Command::Analyze => {
+            let module_name = format!("day{:02}", options.debug_day().unwrap());
+            if options.input == InputSource::Stdin {
+                eprintln!(
+                    "error: --debug, --profile and --analyze cannot read stdin, use --input instead"
+                );
+                std::process::exit(2);
+            }
+            // The default input, or the first named one if there is none
+            let inputs = inputs::collect(&options.input, Path::new("./data"), &module_name, None);
+            let result = match &inputs[0].contents {
+                Ok(input) => load_program(input, &options).and_then(|(program, registers)| {
+                    if options.command == Command::Debug {
+                        debugger::run(program, registers)
+                    } else if options.command == Command::Analyze {
+                        let mut initial = vec![None; registers.len()];
+                        for &(register, value) in &options.registers {
+                            initial[register] = Some(value);
+                        }
+                        println!("{}", symbolic::analyze(&program, &initial));
+                        Ok(())
+                    } else {
+                        profiler::run(
+                            program,
+                            registers,
+                            options.trace.as_deref(),
+                            options.timeout,
+                        )
+                    }
+                }),
+                Err(err) => Err(anyhow!("{}", err)),
+            };
+            if let Err(err) = result {
+                eprintln!("error: {:#}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Command::List => {
+            for (module_name, parts) in &day_list {
+                let part_names: Vec<_> = parts.iter().map(|part| part.part_name).collect();
+                println!("{} {}", module_name, part_names.join(" "));
+                for part in parts {
+                    for param in part.params {
+                        println!("  {} {} = {}", part.part_name, param.name, param.default);
+                    }
+                }
             }
+            return;
+        }
+    }
+
+    if let Some(selector) =
+        options.unmatched_selector(day_list.iter().flat_map(|(module_name, parts)| {
+            parts.iter().map(move |part| (*module_name, part.part_name))
+        }))
+    {
+        eprintln!("error: {} does not match any registered part", selector);
+        std::process::exit(2);
+    }
+
+    if let Some(param) = options.params.iter().find(|param| {
+        !day_list.iter().flat_map(|(_, parts)| parts).any(|part| {
+            options.is_selected(part.module_name, part.part_name)
+                && param.applies_to(part)
+                && part.has_param(&param.name)
         })
-        .flat_map(|(module_name, parts)| {
-            let input = std::fs::read_to_string(format!("./data/{}.txt", module_name));
+    }) {
+        eprintln!(
+            "error: no selected part has a parameter matching --param {}",
+            param.name
+        );
+        std::process::exit(2);
+    }
 
+    let stdin_input = match options.input {
+        InputSource::Stdin => {
+            use std::io::Read;
+            let mut input = String::new();
+            if let Err(err) = std::io::stdin().read_to_string(&mut input) {
+                eprintln!("error: cannot read input from stdin ({})", err);
+                std::process::exit(1);
+            }
+            Some(input)
+        }
+        _ => None,
+    };
+    let (mut task_trackers, task_work): (Vec<_>, Vec<_>) = day_list
+        .iter()
+        .filter(|&&(module_name, ref parts)| {
             parts
                 .iter()
-                .cloned()
-                .map(move |(part_name, function)| match &input {
-                    Ok(input) => (
-                        TaskTracker {
-                            module_name,
-                            part_name,
-                            state: TaskState::Pending,
-                            output: None,
-                        },
-                        Some(TaskWork {
-                            input: input.clone(),
-                            function,
-                        }),
-                    ),
-                    Err(err) => (
-                        TaskTracker {
-                            module_name,
-                            part_name,
-                            state: TaskState::Done,
-                            output: Some(Err(anyhow!(
-                                "cannot read input file ./data/{}.txt ({})",
-                                module_name,
-                                err
-                            ))),
-                        },
-                        None,
-                    ),
-                })
+                .any(|part| options.is_selected(module_name, part.part_name))
+        })
+        .flat_map(|&(module_name, ref parts)| {
+            let inputs = inputs::collect(
+                &options.input,
+                Path::new("./data"),
+                module_name,
+                stdin_input.as_deref(),
+            );
+            let options = &options;
+
+            inputs.into_iter().flat_map(move |mut input| {
+                if let Some(param) = input.params.iter().find(|param| {
+                    !parts
+                        .iter()
+                        .any(|part| param.applies_to(part) && part.has_param(&param.name))
+                }) {
+                    input.contents = Err(format!(
+                        "no part of {} has a parameter matching {} for input {}",
+                        module_name, param.name, input.name
+                    ));
+                }
+                parts
+                    .iter()
+                    .filter(move |part| options.is_selected(module_name, part.part_name))
+                    .map(move |part| {
+                        let part_name = part.part_name;
+                        let cancel = CancellationToken::new();
+                        let input_name = input.name.clone();
+                        let params =
+                            Params::for_part(part, input.params.iter().chain(&options.params));
+                        let params_overridden = !Params::for_part(part, &options.params)
+                            .overridden()
+                            .is_empty();
+                        match &input.contents {
+                            Ok(input) => (
+                                TaskTracker {
+                                    module_name,
+                                    part_name,
+                                    input_name,
+                                    state: TaskState::Pending,
+                                    output: None,
+                                    timings: Vec::new(),
+                                    verdict: None,
+                                    params_overridden,
+                                    started: None,
+                                    cancel: cancel.clone(),
+                                },
+                                Some(TaskWork {
+                                    input: input.clone(),
+                                    params,
+                                    function: part.solver,
+                                    cancel,
+                                }),
+                            ),
+                            Err(err) => (
+                                TaskTracker {
+                                    module_name,
+                                    part_name,
+                                    input_name,
+                                    state: TaskState::Done,
+                                    output: Some(Err(anyhow!("{}", err))),
+                                    timings: Vec::new(),
+                                    verdict: None,
+                                    params_overridden,
+                                    started: None,
+                                    cancel,
+                                },
+                                None,
+                            ),
+                        }
+                    })
+            })
         })
         .unzip();
 
-    if task_trackers.len() == 0 {
+    if task_trackers.is_empty() {
         println!("No tasks to run");
         return;
     }
@@ -302,48 +617,113 @@ fn main() {
 
     let work_count = task_work.iter().filter(|work| work.is_some()).count();
     let work_queue = Arc::new(Mutex::new(task_work));
-    let worker_threads = (0..(num_cpus::get() - 1).max(1).min(work_count))
-        .map(|thread_idx| {
-            let work_queue = work_queue.clone();
-            let result_sender = result_sender.clone();
-            thread::Builder::new()
-                .name(format!("worker thread {}", thread_idx))
-                .spawn(move || {
-                    while let Some((task_index, work)) = pop_work(&work_queue) {
-                        result_sender
-                            .send(TaskUpdate::WorkStarted(task_index))
-                            .unwrap();
-                        let result = std::panic::catch_unwind(move || (work.function)(&work.input))
-                            .unwrap_or_else(|_| Err(anyhow!("task panicked")));
-                        result_sender
-                            .send(TaskUpdate::WorkDone(task_index, result))
-                            .unwrap();
-                    }
-                })
-                .unwrap()
-        })
+    let thread_count = options
+        .threads
+        .unwrap_or_else(|| (num_cpus::get() - 1).max(1));
+    let runs = options.runs;
+    let mut worker_threads = (0..thread_count.min(work_count))
+        .map(|thread_idx| spawn_worker(thread_idx, work_queue.clone(), result_sender.clone(), runs))
         .collect::<Vec<_>>();
 
+    // Progress is only shown when writing the human-readable output, so that
+    // machine-readable output on stdout stays parseable.
+    let show_progress = options.format == Format::Text;
     let stdout = std::io::stdout();
-    init_progress(&mut stdout.lock()).unwrap();
+    if show_progress {
+        init_progress(&mut stdout.lock()).unwrap();
+    }
     let mut work_left = work_count;
+    let mut timed_out_count = 0;
     while work_left > 0 {
-        match result_receiver.recv().unwrap() {
-            TaskUpdate::WorkStarted(idx) => {
+        let deadline = options.timeout.and_then(|timeout| {
+            task_trackers
+                .iter()
+                .filter(|task| task.state == TaskState::Running)
+                .filter_map(|task| task.started)
+                .min()
+                .map(|started| started + timeout)
+        });
+        let update = match deadline {
+            Some(deadline) => {
+                match result_receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                {
+                    Ok(update) => Some(update),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(err) => panic!("{}", err),
+                }
+            }
+            None => Some(result_receiver.recv().unwrap()),
+        };
+        match update {
+            Some(TaskUpdate::WorkStarted(idx)) => {
                 task_trackers[idx].state = TaskState::Running;
+                task_trackers[idx].started = Some(Instant::now());
             }
-            TaskUpdate::WorkDone(idx, result) => {
+            // Results of tasks that already timed out are discarded
+            Some(TaskUpdate::WorkDone(idx, _, _))
+                if task_trackers[idx].state == TaskState::Done => {}
+            Some(TaskUpdate::WorkDone(idx, result, timings)) => {
                 let task = &mut task_trackers[idx];
                 task.state = TaskState::Done;
                 task.output = Some(result);
+                task.timings = timings;
                 work_left -= 1;
             }
+            None => {
+                let timeout = options.timeout.unwrap();
+                let now = Instant::now();
+                for task in task_trackers.iter_mut().filter(|task| {
+                    task.state == TaskState::Running && task.started.unwrap() + timeout <= now
+                }) {
+                    task.cancel.cancel();
+                    task.state = TaskState::Done;
+                    task.output = Some(Err(anyhow!("timed out after {}", FormatDuration(timeout))));
+                    work_left -= 1;
+
+                    // The solution might never poll its cancellation token, so
+                    // its worker thread is replaced to let the remaining work finish.
+                    worker_threads.push(spawn_worker(
+                        worker_threads.len(),
+                        work_queue.clone(),
+                        result_sender.clone(),
+                        runs,
+                    ));
+                    timed_out_count += 1;
+                }
+            }
+        }
+        if show_progress {
+            update_progress(&mut stdout.lock(), &task_trackers).unwrap();
+        }
+    }
+    // Answers are only compared if asked for, or if there is a file to compare against.
+    if options.check || options.record || options.answers.exists() {
+        if let Err(err) = check_answers(&options, &mut task_trackers) {
+            eprintln!("error: {:#}", err);
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(err) = write_output(&options, &task_trackers) {
+        eprintln!("error: cannot write output ({})", err);
+        std::process::exit(1);
+    }
+
+    // Worker threads stuck in a timed out task cannot be joined, they are
+    // terminated when the process exits.
+    if timed_out_count == 0 {
+        for worker_thread in worker_threads {
+            worker_thread.join().unwrap();
         }
-        update_progress(&mut stdout.lock(), &task_trackers).unwrap();
     }
-    output_results(&mut stdout.lock(), &task_trackers).unwrap();
 
-    for worker_thread in worker_threads {
-        worker_thread.join().unwrap();
+    if options.check
+        && task_trackers.iter().any(|task| {
+            task.output.as_ref().unwrap().is_err()
+                || matches!(task.verdict, Some(Verdict::Fail { .. }))
+        })
+    {
+        std::process::exit(1);
     }
 }
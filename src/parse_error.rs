@@ -0,0 +1,234 @@
+use nom::error::{Error, ErrorKind, VerboseError, VerboseErrorKind};
+use std::fmt::{self, Display, Formatter};
+
+/// Position of an error within the puzzle input, 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    /// The full line containing the error, without its line ending.
+    pub text: String,
+}
+
+/// A parser failure, pointing at the line and column where it occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub location: Option<Location>,
+    /// Enclosing parsers or contexts, innermost first.
+    pub stack: Vec<(String, Location)>,
+}
+
+/// Nom error types that can be turned into a [`ParseError`], as a list of the
+/// remaining input and a description, innermost first.
+pub trait ErrorEntries<'s> {
+    fn entries(&self) -> Vec<(&'s str, String)>;
+}
+
+fn describe_kind(kind: ErrorKind) -> String {
+    format!("{:?} failed", kind)
+}
+
+impl<'s> ErrorEntries<'s> for Error<&'s str> {
+    fn entries(&self) -> Vec<(&'s str, String)> {
+        vec![(self.input, describe_kind(self.code))]
+    }
+}
+
+impl<'s> ErrorEntries<'s> for (&'s str, ErrorKind) {
+    fn entries(&self) -> Vec<(&'s str, String)> {
+        vec![(self.0, describe_kind(self.1))]
+    }
+}
+
+impl<'s> ErrorEntries<'s> for VerboseError<&'s str> {
+    fn entries(&self) -> Vec<(&'s str, String)> {
+        self.errors
+            .iter()
+            .map(|(input, kind)| {
+                let description = match kind {
+                    VerboseErrorKind::Context(context) => format!("in {}", context),
+                    VerboseErrorKind::Char(c) => format!("expected {:?}", c),
+                    VerboseErrorKind::Nom(kind) => describe_kind(*kind),
+                };
+                (*input, description)
+            })
+            .collect()
+    }
+}
+
+/// Locates `remaining`, which must be a slice of `input`, such as a suffix of
+/// the trimmed input the parser ran on.
+fn locate(input: &str, remaining: &str) -> Location {
+    let offset = (remaining.as_ptr() as usize).wrapping_sub(input.as_ptr() as usize);
+    locate_offset(input, offset.min(input.len()))
+}
+
+fn locate_offset(input: &str, offset: usize) -> Location {
+    let line_start = input[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = input[offset..]
+        .find('\n')
+        .map_or(input.len(), |idx| offset + idx);
+    Location {
+        line: input[..offset].matches('\n').count() + 1,
+        column: input[line_start..offset].chars().count() + 1,
+        text: input[line_start..line_end]
+            .trim_end_matches('\r')
+            .to_owned(),
+    }
+}
+
+impl ParseError {
+    fn from_entries(input: &str, entries: Vec<(&str, String)>) -> Self {
+        let mut entries = entries
+            .into_iter()
+            .map(|(remaining, description)| (description, locate(input, remaining)));
+        match entries.next() {
+            Some((message, location)) => ParseError {
+                message,
+                location: Some(location),
+                stack: entries.collect(),
+            },
+            None => ParseError {
+                message: "parse error".to_owned(),
+                location: None,
+                stack: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Runs a parser on the trimmed input, requiring it to consume all of it.
+pub fn parse_all<'s, P, T, E>(input: &'s str, parser: P) -> Result<T, ParseError>
+where
+    P: FnOnce(&'s str) -> nom::IResult<&'s str, T, E>,
+    E: ErrorEntries<'s>,
+{
+    let trimmed = input.trim();
+    match parser(trimmed) {
+        Ok((remainder, value)) => {
+            if remainder.is_empty() {
+                Ok(value)
+            } else {
+                Err(ParseError {
+                    message: "unexpected input after the parsed value".to_owned(),
+                    location: Some(locate(input, remainder.trim_start())),
+                    stack: Vec::new(),
+                })
+            }
+        }
+        Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => {
+            Err(ParseError::from_entries(input, err.entries()))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(ParseError {
+            message: "unexpected end of input".to_owned(),
+            location: Some(locate(input, &trimmed[trimmed.len()..])),
+            stack: Vec::new(),
+        }),
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let location = match &self.location {
+            Some(location) => location,
+            None => return f.write_str(&self.message),
+        };
+        writeln!(
+            f,
+            "line {}, column {}: {}",
+            location.line, location.column, self.message
+        )?;
+        let gutter = location.line.to_string().len();
+        writeln!(f, "{:gutter$} |", "", gutter = gutter)?;
+        writeln!(f, "{} | {}", location.line, location.text)?;
+        write!(
+            f,
+            "{:gutter$} | {:column$}^",
+            "",
+            "",
+            gutter = gutter,
+            column = location.column - 1
+        )?;
+        for (description, location) in &self.stack {
+            write!(
+                f,
+                "\n{:gutter$} = {} (line {}, column {})",
+                "",
+                description,
+                location.line,
+                location.column,
+                gutter = gutter
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[test]
+fn parse_error() -> anyhow::Result<()> {
+    use crate::parsers::*;
+    use nom::error::context;
+
+    fn numbers(s: &str) -> IResult<&str, Vec<u32>> {
+        separated_list1(line_ending, u32_str)(s)
+    }
+    assert_eq!(parse_all("\n1\n2\n", numbers)?, vec![1, 2]);
+
+    let err = parse_all("1\n2\n3x\n4", numbers).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "\
+line 3, column 2: unexpected input after the parsed value
+  |
+3 | 3x
+  |  ^"
+    );
+
+    let err = parse_all("12, 7, ab", |s| -> IResult<&str, Vec<u32>> {
+        separated_list1(tag(", "), u32_str)(s)
+    })
+    .unwrap_err();
+    assert_eq!(err.location.map(|l| (l.line, l.column)), Some((1, 6)));
+
+    // Locations are unaffected by the whitespace trimmed from the input
+    fn actions(s: &str) -> IResult<&str, Vec<(char, &str)>> {
+        separated_list1(tag(", "), pair(one_of("LR"), digit1))(s)
+    }
+    for (input, column) in [
+        ("R2, L3, X5\n", 7),
+        ("R2, L3, X5\n\n\n\n", 7),
+        ("  R2, L3, X5", 9),
+    ] {
+        let err = parse_all(input, actions).unwrap_err();
+        let location = err.location.unwrap();
+        assert_eq!((location.line, location.column), (1, column));
+    }
+    let err = parse_all("R2, L3\u{e9}\n", actions).unwrap_err();
+    assert_eq!(err.location.map(|l| (l.line, l.column)), Some((1, 7)));
+    let err = parse_all("\u{e9}\nR2, X\n", actions).unwrap_err();
+    assert_eq!(err.location.map(|l| (l.line, l.column)), Some((1, 1)));
+
+    let err = parse_all("\nR8\nX4", |s| -> IResult<&str, _, VerboseError<&str>> {
+        separated_list1(line_ending, context("action", pair(one_of("LR"), digit1)))(s)
+    })
+    .unwrap_err();
+    assert_eq!(err.location.as_ref().map(|l| l.line), Some(3));
+    let err = parse_all("X4", |s| -> IResult<&str, _, VerboseError<&str>> {
+        context("action", pair(one_of("LR"), digit1))(s)
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "\
+line 1, column 1: OneOf failed
+  |
+1 | X4
+  | ^
+  = in action (line 1, column 1)"
+    );
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
+pub(crate) use crate::cancel;
 pub(crate) use crate::mat2::Mat2;
 pub(crate) use crate::parsers;
 pub(crate) use crate::vec2::Vec2us;
 pub(crate) use anyhow::{anyhow, Result};
+pub(crate) use aoc_proc_macro::aoc;
 pub(crate) use arrayvec::ArrayVec;
 pub(crate) use itertools::Itertools;
 pub(crate) use nom::IResult;
@@ -39,7 +41,7 @@ impl Display for Parts {
     }
 }
 
-pub(crate) trait IterEx: Iterator + Sized {
+pub trait IterEx: Iterator + Sized {
     fn limit<F>(self, count: usize, append_if_limited: F) -> LimitIter<Self, F>
     where
         F: FnOnce() -> Self::Item,
@@ -65,11 +67,7 @@ impl<I: Iterator, F: FnOnce() -> I::Item> Iterator for LimitIter<I, F> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.remainder == 0 {
-            if let Some(factory) = self.append_if_limited.take() {
-                Some(factory())
-            } else {
-                None
-            }
+            self.append_if_limited.take().map(|factory| factory())
         } else {
             if let Some(item) = self.iter.next() {
                 self.remainder -= 1;
@@ -0,0 +1,69 @@
+use advent_of_code_2016::assembunny::{Profile, Program};
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Number of steps between checks of the timeout
+const TIMEOUT_INTERVAL: u64 = 1 << 16;
+
+// Number of transmitted values that are printed
+const MAX_OUTPUT: usize = 32;
+
+/// Runs the program until it halts, waits for input or the timeout expires,
+/// then prints the annotated listing.
+pub fn run(
+    mut program: Program,
+    mut registers: Vec<i64>,
+    trace: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let mut trace = match trace {
+        Some(path) => Some(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("cannot create trace file {}", path.display()))?,
+        )),
+        None => None,
+    };
+
+    let start = Instant::now();
+    let mut profile = Profile::default();
+    let mut output = Vec::new();
+    let mut timed_out = false;
+    while program.run_profiled(
+        &mut registers,
+        &mut profile,
+        trace.as_mut().map(|trace| trace as &mut dyn Write),
+        |value| {
+            if output.len() < MAX_OUTPUT {
+                output.push(value.to_string())
+            }
+        },
+    )? {
+        if profile.steps % TIMEOUT_INTERVAL == 0
+            && timeout.is_some_and(|timeout| start.elapsed() >= timeout)
+        {
+            timed_out = true;
+            break;
+        }
+    }
+    if let Some(mut trace) = trace {
+        trace.flush()?;
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "{}", profile.annotate(&program))?;
+    if !output.is_empty() {
+        writeln!(out, "out: {}", output.join(","))?;
+    }
+    if timed_out {
+        writeln!(out, "stopped after the timeout of {:?}", timeout.unwrap())?;
+    } else if program.is_waiting_for_input() {
+        writeln!(out, "stopped at an in instruction, all input has been read")?;
+    } else {
+        let registers = registers.iter().map(i64::to_string).collect::<Vec<_>>();
+        writeln!(out, "halted with registers {}", registers.join(","))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use crate::parse_error::{parse_all, ErrorEntries};
+use crate::prelude::Parts;
+use anyhow::{anyhow, Context, Result};
+use std::fmt::Display;
+use std::str::FromStr;
+
+pub type Solver = fn(&str, &Params) -> Result<Answer>;
+
+/// The answer of a part, or of both parts for functions solving them at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Single(String),
+    Both(String, String),
+}
+
+impl Answer {
+    pub fn single<R: Display>(answer: R) -> Self {
+        Answer::Single(answer.to_string())
+    }
+
+    pub fn both(Parts(pt1, pt2): Parts) -> Self {
+        Answer::Both(pt1, pt2)
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Answer::Single(answer) => f.write_str(answer),
+            Answer::Both(pt1, pt2) => Parts(pt1.clone(), pt2.clone()).fmt(f),
+        }
+    }
+}
+
+/// A named parameter of a part, declared with `params(name = default)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Param {
+    pub name: &'static str,
+    pub default: &'static str,
+}
+
+/// A part of a day, registered through the `#[aoc(...)]` attribute.
+pub struct Part {
+    pub module_name: &'static str,
+    pub part_name: &'static str,
+    pub day: u32,
+    /// Puzzle parts this solves, `[1, 2]` for functions solving both.
+    pub parts: &'static [u32],
+    pub params: &'static [Param],
+    pub solver: Solver,
+}
+
+impl Part {
+    pub fn has_param(&self, name: &str) -> bool {
+        self.params.iter().any(|param| param.name == name)
+    }
+}
+
+/// Overrides the default value of a parameter, written as
+/// `[dayXX:][part:]name=value`. Without a day or part it applies to every part
+/// declaring a parameter with that name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamOverride {
+    pub day: Option<u32>,
+    pub part_name: Option<String>,
+    pub name: String,
+    pub value: String,
+}
+
+impl ParamOverride {
+    pub fn applies_to(&self, part: &Part) -> bool {
+        self.day.is_none_or(|day| day == part.day)
+            && self
+                .part_name
+                .as_ref()
+                .is_none_or(|part_name| part_name == part.part_name)
+    }
+
+    /// Parses a parameter file, containing one `[part:]name = value` per line.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse_file(s: &str) -> Result<Vec<ParamOverride>> {
+        s.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|(line_idx, line)| {
+                line.parse()
+                    .with_context(|| format!("line {}", line_idx + 1))
+            })
+            .collect()
+    }
+}
+
+impl FromStr for ParamOverride {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (target, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected [dayXX:][part:]name=value, got {:?}", s))?;
+        let mut target = target.trim().split(':').collect::<Vec<_>>();
+        let name = target.pop().unwrap().to_owned();
+        let day = match target.first() {
+            Some(module_name) if module_name.starts_with("day") => {
+                let day = day_number(module_name)
+                    .ok_or_else(|| anyhow!("invalid day {:?}", module_name))?;
+                target.remove(0);
+                Some(day)
+            }
+            _ => None,
+        };
+        let part_name = match target.as_slice() {
+            [] => None,
+            [part_name] => Some((*part_name).to_owned()),
+            _ => return Err(anyhow!("expected [dayXX:][part:]name=value, got {:?}", s)),
+        };
+        if name.is_empty() {
+            return Err(anyhow!("missing parameter name in {:?}", s));
+        }
+        Ok(ParamOverride {
+            day,
+            part_name,
+            name,
+            value: value.trim().to_owned(),
+        })
+    }
+}
+
+/// Parameter values a part runs with, falling back to the declared defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+    values: Vec<(String, String)>,
+}
+
+impl Params {
+    /// Collects the overrides applying to a part, later overrides win.
+    pub fn for_part<'a, I>(part: &Part, overrides: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ParamOverride>,
+    {
+        let mut params = Params::default();
+        for param_override in overrides {
+            if param_override.applies_to(part) && part.has_param(&param_override.name) {
+                params
+                    .values
+                    .retain(|(name, _)| *name != param_override.name);
+                params
+                    .values
+                    .push((param_override.name.clone(), param_override.value.clone()));
+            }
+        }
+        params
+    }
+
+    /// The overridden values, in the order they were applied.
+    pub fn overridden(&self) -> &[(String, String)] {
+        &self.values
+    }
+
+    pub fn get<T>(&self, name: &str, default: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Into<anyhow::Error>,
+    {
+        let value = self
+            .values
+            .iter()
+            .find(|(param_name, _)| param_name == name)
+            .map_or(default, |(_, value)| value.as_str());
+        value
+            .parse()
+            .map_err(Into::into)
+            .with_context(|| format!("invalid value {:?} for parameter {}", value, name))
+    }
+}
+
+inventory::collect!(Part);
+
+/// Occupied by every registered part, `#[aoc(day = 7, part = 2)]` implements
+/// `Slot<7, 2>`. Two parts claiming the same day and part therefore fail to
+/// compile with conflicting implementations of this trait.
+pub trait Slot<const DAY: u32, const PART: u32> {}
+pub struct Registry;
+
+/// Extracts the day number from a module name such as `day07`.
+pub fn day_number(module_name: &str) -> Option<u32> {
+    module_name.strip_prefix("day")?.parse().ok()
+}
+
+/// All registered parts, grouped by module and ordered by day and part.
+pub fn day_list() -> Vec<(&'static str, Vec<&'static Part>)> {
+    let mut parts = inventory::iter::<Part>.into_iter().collect::<Vec<_>>();
+    parts.sort_by_key(|part| (part.day, part.parts[0], part.part_name));
+
+    let mut day_list: Vec<(&'static str, Vec<&'static Part>)> = Vec::new();
+    for part in parts {
+        match day_list.last_mut() {
+            Some((module_name, module_parts)) if *module_name == part.module_name => {
+                module_parts.push(part)
+            }
+            _ => day_list.push((part.module_name, vec![part])),
+        }
+    }
+    day_list
+}
+
+/// Runs a part that takes the raw puzzle input.
+pub fn solve_raw<F, R>(input: &str, part: F) -> Result<R>
+where
+    F: FnOnce(&str) -> Result<R>,
+{
+    part(input.trim())
+}
+
+/// Runs a part on the output of its parser, which must consume all input.
+pub fn solve_parsed<'s, P, F, T, R, E>(input: &'s str, parser: P, part: F) -> Result<R>
+where
+    P: FnOnce(&'s str) -> nom::IResult<&'s str, T, E>,
+    F: FnOnce(T) -> Result<R>,
+    E: ErrorEntries<'s>,
+{
+    part(parse_all(input, parser)?)
+}
+
+#[test]
+fn registry() -> Result<()> {
+    let day16 = day_list()
+        .into_iter()
+        .find(|(module_name, _)| *module_name == "day16")
+        .unwrap()
+        .1;
+    let (pt1, pt2) = (day16[0], day16[1]);
+    assert_eq!(pt2.params[0].name, "disk_size");
+
+    let overrides = [
+        "disk_size=12".parse::<ParamOverride>()?,
+        "day16:pt2:disk_size=20".parse()?,
+        "day17:disk_size=1".parse()?,
+    ];
+    let params = Params::for_part(pt1, &overrides);
+    assert_eq!(params.get::<usize>("disk_size", "272")?, 12);
+    let params = Params::for_part(pt2, &overrides);
+    assert_eq!(params.get::<usize>("disk_size", "272")?, 20);
+    assert_eq!(Params::default().get::<usize>("disk_size", "272")?, 272);
+    assert!(Params::default().get::<usize>("disk_size", "x").is_err());
+    assert_eq!(
+        (pt2.solver)("10000", &params)?,
+        Answer::Single("01100".to_owned())
+    );
+
+    assert_eq!(
+        ParamOverride::parse_file("# comment\n\npt1:start = abcde\n")?,
+        vec![ParamOverride {
+            day: None,
+            part_name: Some("pt1".to_owned()),
+            name: "start".to_owned(),
+            value: "abcde".to_owned(),
+        }]
+    );
+    assert!("day16".parse::<ParamOverride>().is_err());
+    assert!("dayx:size=1".parse::<ParamOverride>().is_err());
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use crate::answers::Verdict;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Version of the JSON lines and CSV layouts, bumped whenever the layout
+/// changes, including added fields, which shift the CSV columns.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    JsonLines,
+    Csv,
+}
+
+impl std::str::FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" | "jsonl" => Ok(Format::JsonLines),
+            "csv" => Ok(Format::Csv),
+            _ => Err(anyhow::anyhow!(
+                "unknown format {:?}, expected text, json or csv",
+                s
+            )),
+        }
+    }
+}
+
+/// The outcome of a single part, as it is written to a report.
+pub struct Record<'a> {
+    pub module_name: &'a str,
+    pub part_name: &'a str,
+    pub input_name: &'a str,
+    pub output: &'a Result<String, anyhow::Error>,
+    pub timings: &'a [Duration],
+    pub verdict: Option<&'a Verdict>,
+}
+
+/// Minimum, median and maximum of a set of timings.
+pub fn timing_stats(timings: &[Duration]) -> Option<(Duration, Duration, Duration)> {
+    let mut sorted = timings.to_vec();
+    sorted.sort_unstable();
+    Some((*sorted.first()?, sorted[sorted.len() / 2], *sorted.last()?))
+}
+
+/// Removes the terminal styling (ANSI CSI sequences) that answers such as
+/// `Parts` embed, so that machine-readable output only contains the answer.
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            chars.next();
+            for c in &mut chars {
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn error_chain(err: &anyhow::Error) -> impl Iterator<Item = String> + '_ {
+    err.chain().map(|cause| cause.to_string())
+}
+
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn csv_field(out: &mut String, s: &str) {
+    if s.contains(&[',', '"', '\n', '\r'][..]) {
+        out.push('"');
+        out.push_str(&s.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(s);
+    }
+}
+
+fn json_record(record: &Record) -> String {
+    let mut line = String::new();
+    write!(line, "{{\"schema\":{},\"module\":", SCHEMA_VERSION).unwrap();
+    json_string(&mut line, record.module_name);
+    line.push_str(",\"part\":");
+    json_string(&mut line, record.part_name);
+    line.push_str(",\"input\":");
+    json_string(&mut line, record.input_name);
+    match record.output {
+        Ok(answer) => {
+            line.push_str(",\"status\":\"ok\",\"answer\":");
+            json_string(&mut line, &strip_ansi(answer));
+            line.push_str(",\"error\":null");
+        }
+        Err(err) => {
+            line.push_str(",\"status\":\"error\",\"answer\":null,\"error\":[");
+            for (idx, cause) in error_chain(err).enumerate() {
+                if idx > 0 {
+                    line.push(',');
+                }
+                json_string(&mut line, &cause);
+            }
+            line.push(']');
+        }
+    }
+    write!(line, ",\"runs\":{}", record.timings.len()).unwrap();
+    match timing_stats(record.timings) {
+        Some((min, median, max)) => write!(
+            line,
+            ",\"min_ns\":{},\"median_ns\":{},\"max_ns\":{}",
+            min.as_nanos(),
+            median.as_nanos(),
+            max.as_nanos()
+        )
+        .unwrap(),
+        None => line.push_str(",\"min_ns\":null,\"median_ns\":null,\"max_ns\":null"),
+    }
+    match record.verdict {
+        Some(verdict) => {
+            write!(line, ",\"check\":\"{}\",\"expected\":", verdict.name()).unwrap();
+            match verdict {
+                Verdict::Fail { expected } => json_string(&mut line, expected),
+                _ => line.push_str("null"),
+            }
+        }
+        None => line.push_str(",\"check\":null,\"expected\":null"),
+    }
+    line.push('}');
+    line
+}
+
+const CSV_HEADER: &str =
+    "schema,module,part,input,status,answer,error,runs,min_ns,median_ns,max_ns,check,expected";
+
+fn csv_record(record: &Record) -> String {
+    let mut line = format!("{},", SCHEMA_VERSION);
+    csv_field(&mut line, record.module_name);
+    line.push(',');
+    csv_field(&mut line, record.part_name);
+    line.push(',');
+    csv_field(&mut line, record.input_name);
+    match record.output {
+        Ok(answer) => {
+            line.push_str(",ok,");
+            csv_field(&mut line, &strip_ansi(answer));
+            line.push(',');
+        }
+        Err(err) => {
+            line.push_str(",error,,");
+            csv_field(&mut line, &error_chain(err).collect::<Vec<_>>().join(": "));
+        }
+    }
+    write!(line, ",{}", record.timings.len()).unwrap();
+    match timing_stats(record.timings) {
+        Some((min, median, max)) => write!(
+            line,
+            ",{},{},{}",
+            min.as_nanos(),
+            median.as_nanos(),
+            max.as_nanos()
+        )
+        .unwrap(),
+        None => line.push_str(",,,"),
+    }
+    line.push(',');
+    if let Some(verdict) = record.verdict {
+        line.push_str(verdict.name());
+    }
+    line.push(',');
+    if let Some(Verdict::Fail { expected }) = record.verdict {
+        csv_field(&mut line, expected);
+    }
+    line
+}
+
+/// Writes the records in a machine-readable format, one record per line.
+pub fn write_records<'a, W, I>(out: &mut W, format: Format, records: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = Record<'a>>,
+{
+    match format {
+        Format::Text => unreachable!("text output is written by the interactive runner"),
+        Format::JsonLines => {
+            for record in records {
+                writeln!(out, "{}", json_record(&record))?;
+            }
+        }
+        Format::Csv => {
+            writeln!(out, "{}", CSV_HEADER)?;
+            for record in records {
+                writeln!(out, "{}", csv_record(&record))?;
+            }
+        }
+    }
+    out.flush()
+}
+
+#[test]
+fn report() -> anyhow::Result<()> {
+    use advent_of_code_2016::prelude::ToParts;
+    use anyhow::Context;
+
+    let ok = Ok(format!("{}", ("multi", "\"line\"").to_parts()));
+    let err: Result<String, _> = Err(anyhow::anyhow!("no path found")).context("while pathfinding");
+    let timings = [
+        Duration::from_nanos(30),
+        Duration::from_nanos(10),
+        Duration::from_nanos(20),
+    ];
+    let fail = Verdict::Fail {
+        expected: "12".to_owned(),
+    };
+    let records = || {
+        vec![
+            Record {
+                module_name: "day01",
+                part_name: "pt1",
+                input_name: "default",
+                output: &ok,
+                timings: &timings,
+                verdict: None,
+            },
+            Record {
+                module_name: "day02",
+                part_name: "pt2",
+                input_name: "alice",
+                output: &err,
+                timings: &[],
+                verdict: Some(&fail),
+            },
+        ]
+    };
+
+    let mut json = Vec::new();
+    write_records(&mut json, Format::JsonLines, records())?;
+    assert_eq!(
+        String::from_utf8(json)?,
+        "\
+{\"schema\":2,\"module\":\"day01\",\"part\":\"pt1\",\"input\":\"default\",\"status\":\"ok\",\"answer\":\"pt1 multi\\npt2 \\\"line\\\"\",\"error\":null,\"runs\":3,\"min_ns\":10,\"median_ns\":20,\"max_ns\":30,\"check\":null,\"expected\":null}
+{\"schema\":2,\"module\":\"day02\",\"part\":\"pt2\",\"input\":\"alice\",\"status\":\"error\",\"answer\":null,\"error\":[\"while pathfinding\",\"no path found\"],\"runs\":0,\"min_ns\":null,\"median_ns\":null,\"max_ns\":null,\"check\":\"fail\",\"expected\":\"12\"}
+"
+    );
+
+    let mut csv = Vec::new();
+    write_records(&mut csv, Format::Csv, records())?;
+    assert_eq!(
+        String::from_utf8(csv)?,
+        "\
+schema,module,part,input,status,answer,error,runs,min_ns,median_ns,max_ns,check,expected
+2,day01,pt1,default,ok,\"pt1 multi\npt2 \"\"line\"\"\",,3,10,20,30,,
+2,day02,pt2,alice,error,,while pathfinding: no path found,0,,,,fail,12
+"
+    );
+
+    Ok(())
+}
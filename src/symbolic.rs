@@ -0,0 +1,995 @@
+use crate::assembunny::{Instruction, Program, Value};
+use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+// Largest number of instructions executed symbolically, over all paths
+const MAX_STEPS: u64 = 1_000_000;
+// Largest number of paths followed after branches on unknown registers
+const MAX_PATHS: usize = 64;
+
+/// A polynomial in the initial registers, with integer coefficients.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Poly {
+    // Coefficient of each monomial, written as the sorted registers it
+    // multiplies, zero coefficients are left out
+    terms: BTreeMap<Vec<usize>, i64>,
+}
+
+impl Poly {
+    pub fn constant(value: i64) -> Self {
+        let mut terms = BTreeMap::new();
+        if value != 0 {
+            terms.insert(Vec::new(), value);
+        }
+        Poly { terms }
+    }
+
+    /// The initial value of a register.
+    pub fn register(register: usize) -> Self {
+        Poly {
+            terms: std::iter::once((vec![register], 1)).collect(),
+        }
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&Vec::new()).copied(),
+            _ => None,
+        }
+    }
+
+    /// Whether the value depends on the initial value of `register`.
+    pub fn uses(&self, register: usize) -> bool {
+        self.terms
+            .keys()
+            .any(|monomial| monomial.contains(&register))
+    }
+
+    /// The sum, `None` if a coefficient overflows.
+    pub fn checked_add(&self, other: &Poly) -> Option<Poly> {
+        let mut terms = self.terms.clone();
+        for (monomial, &value) in &other.terms {
+            let sum = terms.get(monomial).unwrap_or(&0).checked_add(value)?;
+            if sum == 0 {
+                terms.remove(monomial);
+            } else {
+                terms.insert(monomial.clone(), sum);
+            }
+        }
+        Some(Poly { terms })
+    }
+
+    pub fn checked_sub(&self, other: &Poly) -> Option<Poly> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Poly> {
+        self.checked_mul(&Poly::constant(-1))
+    }
+
+    /// The product, `None` if a coefficient overflows.
+    pub fn checked_mul(&self, other: &Poly) -> Option<Poly> {
+        let mut product = Poly::default();
+        for (lhs, &lhs_value) in &self.terms {
+            for (rhs, &rhs_value) in &other.terms {
+                let mut monomial: Vec<usize> = lhs.iter().chain(rhs).copied().collect();
+                monomial.sort_unstable();
+                let term = Poly {
+                    terms: std::iter::once((monomial, lhs_value.checked_mul(rhs_value)?)).collect(),
+                };
+                product = product.checked_add(&term)?;
+            }
+        }
+        Some(product)
+    }
+
+    /// Replaces the initial value of each register by `values[register]`.
+    pub fn substitute(&self, values: &[Poly]) -> Option<Poly> {
+        let mut result = Poly::default();
+        for (monomial, &value) in &self.terms {
+            let mut term = Poly::constant(value);
+            for &register in monomial {
+                term = term.checked_mul(&values[register])?;
+            }
+            result = result.checked_add(&term)?;
+        }
+        Some(result)
+    }
+
+    /// The value for the given initial registers, wrapping on overflow.
+    pub fn evaluate(&self, registers: &[i64]) -> i64 {
+        self.terms.iter().fold(0i64, |sum, (monomial, &value)| {
+            let term = monomial.iter().fold(value, |product, &register| {
+                product.wrapping_mul(registers[register])
+            });
+            sum.wrapping_add(term)
+        })
+    }
+}
+
+impl Display for Poly {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.terms.is_empty() {
+            return f.write_str("0");
+        }
+        // Highest degree first, the constant last
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|&(monomial, _)| (Reverse(monomial.len()), monomial));
+        for (idx, (monomial, &value)) in terms.into_iter().enumerate() {
+            match (idx, value < 0) {
+                (0, false) => {}
+                (0, true) => f.write_str("-")?,
+                (_, false) => f.write_str(" + ")?,
+                (_, true) => f.write_str(" - ")?,
+            }
+            let magnitude = value.unsigned_abs();
+            if monomial.is_empty() || magnitude != 1 {
+                write!(f, "{}", magnitude)?;
+            }
+            for (factor_idx, (power, &register)) in monomial.iter().dedup_with_count().enumerate() {
+                if factor_idx > 0 || magnitude != 1 {
+                    f.write_str("*")?;
+                }
+                write!(f, "{}", Value::Register(register))?;
+                if power > 1 {
+                    write!(f, "^{}", power)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A condition on the initial registers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Condition {
+    Zero(Poly),
+    NonZero(Poly),
+    Positive(Poly),
+}
+
+impl Condition {
+    pub fn poly(&self) -> &Poly {
+        match self {
+            Condition::Zero(poly) | Condition::NonZero(poly) | Condition::Positive(poly) => poly,
+        }
+    }
+
+    /// Whether the condition holds, if it does not depend on the registers.
+    pub fn holds(&self) -> Option<bool> {
+        let value = self.poly().as_constant()?;
+        Some(match self {
+            Condition::Zero(_) => value == 0,
+            Condition::NonZero(_) => value != 0,
+            Condition::Positive(_) => value > 0,
+        })
+    }
+
+    // Flips the sign of (non-)zero conditions so that equivalent ones compare
+    // equal
+    fn normalized(self) -> Condition {
+        let negative = |poly: &Poly| poly.terms.values().next_back().is_some_and(|&v| v < 0);
+        match self {
+            Condition::Zero(poly) if negative(&poly) => {
+                Condition::Zero(poly.checked_neg().unwrap_or(poly))
+            }
+            Condition::NonZero(poly) if negative(&poly) => {
+                Condition::NonZero(poly.checked_neg().unwrap_or(poly))
+            }
+            condition => condition,
+        }
+    }
+
+    fn substitute(&self, values: &[Poly]) -> Option<Condition> {
+        let poly = self.poly().substitute(values)?;
+        Some(
+            match self {
+                Condition::Zero(_) => Condition::Zero(poly),
+                Condition::NonZero(_) => Condition::NonZero(poly),
+                Condition::Positive(_) => Condition::Positive(poly),
+            }
+            .normalized(),
+        )
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Condition::Zero(poly) => write!(f, "{} == 0", poly),
+            Condition::NonZero(poly) => write!(f, "{} != 0", poly),
+            Condition::Positive(poly) => write!(f, "{} > 0", poly),
+        }
+    }
+}
+
+/// Where control goes after a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Successor {
+    Block(usize),
+    /// Past either end of the program, which halts it.
+    Exit,
+    /// A jump by a register, to any instruction.
+    Unknown,
+}
+
+/// Instructions that are only entered at the first and left at the last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub instructions: Range<usize>,
+    pub successors: Vec<Successor>,
+}
+
+/// Control-flow graph of the instructions of a program, as they are before
+/// any `tgl` runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    pub blocks: Vec<Block>,
+}
+
+/// A natural loop, the blocks that reach a back edge to the header without
+/// passing through the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    /// All blocks of the loop including the header, in program order.
+    pub blocks: Vec<usize>,
+}
+
+impl Cfg {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let len = instructions.len();
+        let jump_target = |idx: usize, offset: i64| {
+            let target = idx as i64 + offset;
+            if target >= 0 && target < len as i64 {
+                Some(target as usize)
+            } else {
+                None
+            }
+        };
+        let mut leaders = vec![false; len];
+        if len > 0 {
+            leaders[0] = true;
+        }
+        for (idx, instruction) in instructions.iter().enumerate() {
+            if let Instruction::JumpIfNotZero(_, target) = instruction {
+                if idx + 1 < len {
+                    leaders[idx + 1] = true;
+                }
+                if let Some(target) = match target {
+                    Value::Constant(offset) => jump_target(idx, *offset),
+                    Value::Register(_) => None,
+                } {
+                    leaders[target] = true;
+                }
+            }
+        }
+        let starts: Vec<usize> = (0..len).filter(|&idx| leaders[idx]).collect();
+        let block_of = |idx: usize| starts.partition_point(|&start| start <= idx) - 1;
+        let successor = |target: Option<usize>| match target {
+            Some(target) => Successor::Block(block_of(target)),
+            None => Successor::Exit,
+        };
+
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(block, &start)| {
+                let end = starts.get(block + 1).copied().unwrap_or(len);
+                let last = end - 1;
+                let next = successor(jump_target(last, 1));
+                let mut successors = match instructions[last] {
+                    Instruction::JumpIfNotZero(Value::Constant(0), _) => vec![next],
+                    Instruction::JumpIfNotZero(Value::Constant(_), Value::Constant(offset)) => {
+                        vec![successor(jump_target(last, offset))]
+                    }
+                    Instruction::JumpIfNotZero(Value::Register(_), Value::Constant(offset)) => {
+                        vec![next, successor(jump_target(last, offset))]
+                    }
+                    Instruction::JumpIfNotZero(Value::Constant(_), Value::Register(_)) => {
+                        vec![Successor::Unknown]
+                    }
+                    Instruction::JumpIfNotZero(Value::Register(_), Value::Register(_)) => {
+                        vec![next, Successor::Unknown]
+                    }
+                    _ => vec![next],
+                };
+                successors.dedup();
+                Block {
+                    instructions: start..end,
+                    successors,
+                }
+            })
+            .collect();
+        Cfg { blocks }
+    }
+
+    /// The block containing an instruction.
+    pub fn block_of(&self, idx: usize) -> usize {
+        self.blocks
+            .partition_point(|block| block.instructions.end <= idx)
+    }
+
+    /// Natural loops, ordered by their first block with outer loops first.
+    /// Blocks after a jump by a register are left out, as its targets are
+    /// not known.
+    pub fn loops(&self) -> Vec<Loop> {
+        let len = self.blocks.len();
+        let successors = |block: usize| {
+            self.blocks[block]
+                .successors
+                .iter()
+                .filter_map(|successor| match successor {
+                    Successor::Block(block) => Some(*block),
+                    _ => None,
+                })
+        };
+        let mut reachable = vec![false; len];
+        let mut stack = vec![0];
+        while let Some(block) = stack.pop() {
+            if block < len && !reachable[block] {
+                reachable[block] = true;
+                stack.extend(successors(block));
+            }
+        }
+        let mut predecessors = vec![Vec::new(); len];
+        for block in (0..len).filter(|&block| reachable[block]) {
+            for successor in successors(block) {
+                predecessors[successor].push(block);
+            }
+        }
+
+        // `dominators[block][other]` is whether `other` dominates `block`
+        let mut dominators = vec![vec![true; len]; len];
+        if len > 0 {
+            dominators[0] = (0..len).map(|block| block == 0).collect();
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in (1..len).filter(|&block| reachable[block]) {
+                let mut dominated: Vec<bool> = (0..len)
+                    .map(|other| {
+                        predecessors[block]
+                            .iter()
+                            .all(|&predecessor| dominators[predecessor][other])
+                    })
+                    .collect();
+                dominated[block] = true;
+                if dominated != dominators[block] {
+                    dominators[block] = dominated;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut bodies: BTreeMap<usize, Vec<bool>> = BTreeMap::new();
+        for block in (0..len).filter(|&block| reachable[block]) {
+            for header in successors(block).filter(|&header| dominators[block][header]) {
+                let body = bodies.entry(header).or_insert_with(|| {
+                    let mut body = vec![false; len];
+                    body[header] = true;
+                    body
+                });
+                let mut stack = vec![block];
+                while let Some(block) = stack.pop() {
+                    if !body[block] {
+                        body[block] = true;
+                        stack.extend(&predecessors[block]);
+                    }
+                }
+            }
+        }
+        let mut loops: Vec<Loop> = bodies
+            .into_iter()
+            .map(|(header, body)| Loop {
+                header,
+                blocks: (0..len).filter(|&block| body[block]).collect(),
+            })
+            .collect();
+        loops.sort_by_key(|l| (l.blocks[0], Reverse(l.blocks.len())));
+        loops
+    }
+}
+
+/// One way through the program to its exit, taken when all conditions hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    /// The branches taken on unknown registers, and the loop counts that are
+    /// positive, as a count below one would wrap around instead.
+    pub conditions: Vec<Condition>,
+    pub registers: Vec<Poly>,
+    /// Values transmitted by `out`, in order.
+    pub output: Vec<Poly>,
+}
+
+/// Why the registers at exit could not be derived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    Toggle,
+    Input,
+    /// A jump by a register that depends on the initial registers.
+    ComputedJump,
+    /// Whether a loop repeats depends on this value, and the loop does not
+    /// count a register down to zero.
+    SymbolicLoop(Poly),
+    Overflow,
+    StepLimit,
+    PathLimit,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Reason::Toggle => f.write_str("tgl modifies the program"),
+            Reason::Input => f.write_str("in reads values that are not known ahead of time"),
+            Reason::ComputedJump => f.write_str("the jump offset depends on the initial registers"),
+            Reason::SymbolicLoop(poly) => {
+                write!(f, "whether the loop repeats depends on {}", poly)
+            }
+            Reason::Overflow => f.write_str("a coefficient does not fit in 64 bits"),
+            Reason::StepLimit => write!(f, "no exit within {} steps", MAX_STEPS),
+            Reason::PathLimit => write!(f, "more than {} paths through the program", MAX_PATHS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub at: usize,
+    pub instruction: Instruction,
+    pub reason: Reason,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "instruction {} ({}): {}",
+            self.at, self.instruction, self.reason
+        )
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Result of [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub cfg: Cfg,
+    pub loops: Vec<Loop>,
+    /// Every way through the program, or the first reason none could be
+    /// derived.
+    pub exit: Result<Vec<Case>, Failure>,
+}
+
+/// Derives the registers of a program at exit as polynomials in its initial
+/// registers, of which `initial` fixes the known ones.
+///
+/// Loops that count a register down to zero (or up from below) are
+/// summarized when the other registers change by the same amount each
+/// iteration, other loops are unrolled as long as whether they repeat does
+/// not depend on an unknown register. Branches on unknown registers split
+/// the program into cases.
+pub fn analyze(program: &Program, initial: &[Option<i64>]) -> Analysis {
+    let instructions = program.instructions();
+    let cfg = Cfg::new(instructions);
+    let loops = cfg.loops();
+    let mut executor = Executor {
+        instructions,
+        registers: program.dialect().registers,
+        summaries: HashMap::new(),
+        steps: 0,
+    };
+    let registers = (0..executor.registers)
+        .map(|register| match initial.get(register).copied().flatten() {
+            Some(value) => Poly::constant(value),
+            None => Poly::register(register),
+        })
+        .collect();
+    Analysis {
+        cfg,
+        loops,
+        exit: executor.run(registers),
+    }
+}
+
+// Effect of one iteration of a counted loop, in terms of the registers at the
+// start of the iteration
+#[derive(Debug, Clone)]
+struct Summary {
+    // The `jnz counter` back to the first instruction
+    end: usize,
+    counter: usize,
+    count: Poly,
+    // Conditions of inner loops, which do not change between iterations
+    conditions: Vec<Condition>,
+    updates: Vec<Update>,
+}
+
+#[derive(Debug, Clone)]
+enum Update {
+    Keep,
+    Add(Poly),
+    Set(Poly),
+}
+
+impl Summary {
+    // Runs the loop to its end on `registers`, returning the conditions for
+    // the result to hold, or `None` if the loop cannot be summarized for
+    // these registers
+    fn apply(&self, registers: &mut [Poly]) -> Option<Vec<Condition>> {
+        let count = self.count.substitute(registers)?;
+        let mut conditions = Vec::new();
+        for condition in
+            std::iter::once(Condition::Positive(self.count.clone())).chain(self.conditions.clone())
+        {
+            let condition = condition.substitute(registers)?;
+            match condition.holds() {
+                Some(true) => {}
+                Some(false) => return None,
+                None => conditions.push(condition),
+            }
+        }
+        let mut result = registers.to_vec();
+        for (register, update) in self.updates.iter().enumerate() {
+            match update {
+                Update::Keep => {}
+                Update::Add(delta) => {
+                    result[register] = registers[register]
+                        .checked_add(&count.checked_mul(&delta.substitute(registers)?)?)?
+                }
+                Update::Set(value) => result[register] = value.substitute(registers)?,
+            }
+        }
+        result[self.counter] = Poly::default();
+        registers.clone_from_slice(&result);
+        Some(conditions)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Path {
+    ptr: i64,
+    registers: Vec<Poly>,
+    conditions: Vec<Condition>,
+    output: Vec<Poly>,
+}
+
+impl Path {
+    // Whether `value` is non-zero, if the conditions so far decide it
+    fn decide(&self, value: &Poly) -> Option<bool> {
+        if let Some(value) = value.as_constant() {
+            return Some(value != 0);
+        }
+        let zero = Condition::Zero(value.clone()).normalized();
+        let non_zero = Condition::NonZero(value.clone()).normalized();
+        self.conditions
+            .iter()
+            .find_map(|condition| match condition {
+                _ if *condition == zero => Some(false),
+                _ if *condition == non_zero => Some(true),
+                Condition::Positive(poly)
+                    if Condition::NonZero(poly.clone()).normalized() == non_zero =>
+                {
+                    Some(true)
+                }
+                _ => None,
+            })
+    }
+
+    fn assume(&mut self, condition: Condition) {
+        if !self.conditions.contains(&condition) {
+            self.conditions.push(condition);
+        }
+    }
+}
+
+fn resolve(value: &Value, registers: &[Poly]) -> Poly {
+    match value {
+        Value::Constant(value) => Poly::constant(*value),
+        Value::Register(register) => registers[*register].clone(),
+    }
+}
+
+// Executes an instruction that neither jumps nor reads or writes values,
+// returns `None` on overflow
+fn apply(instruction: &Instruction, registers: &mut [Poly]) -> Option<()> {
+    match instruction {
+        Instruction::Increment(register) => {
+            registers[*register] = registers[*register].checked_add(&Poly::constant(1))?
+        }
+        Instruction::Decrement(register) => {
+            registers[*register] = registers[*register].checked_sub(&Poly::constant(1))?
+        }
+        Instruction::Copy(value, Value::Register(register)) => {
+            registers[*register] = resolve(value, registers)
+        }
+        Instruction::Add(value, Value::Register(register)) => {
+            registers[*register] = registers[*register].checked_add(&resolve(value, registers))?
+        }
+        Instruction::Multiply(value, Value::Register(register)) => {
+            registers[*register] = registers[*register].checked_mul(&resolve(value, registers))?
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+struct Executor<'a> {
+    instructions: &'a [Instruction],
+    registers: usize,
+    // Summary of the loop starting at each instruction, `None` if there is
+    // none
+    summaries: HashMap<usize, Option<Summary>>,
+    steps: u64,
+}
+
+impl Executor<'_> {
+    fn run(&mut self, registers: Vec<Poly>) -> Result<Vec<Case>, Failure> {
+        let mut paths = vec![Path {
+            ptr: 0,
+            registers,
+            conditions: Vec::new(),
+            output: Vec::new(),
+        }];
+        let mut path_count = 1;
+        let mut cases = Vec::new();
+        while let Some(mut path) = paths.pop() {
+            while path.ptr >= 0 && path.ptr < self.instructions.len() as i64 {
+                let idx = path.ptr as usize;
+                let instruction = self.instructions[idx];
+                let fail = |reason| Failure {
+                    at: idx,
+                    instruction,
+                    reason,
+                };
+                self.steps += 1;
+                if self.steps > MAX_STEPS {
+                    return Err(fail(Reason::StepLimit));
+                }
+
+                if let Some(summary) = self.summarize(idx) {
+                    let mut registers = path.registers.clone();
+                    if let Some(conditions) = summary.apply(&mut registers) {
+                        path.registers = registers;
+                        for condition in conditions {
+                            path.assume(condition);
+                        }
+                        path.ptr = summary.end as i64 + 1;
+                        continue;
+                    }
+                }
+
+                match instruction {
+                    Instruction::Toggle(_) => return Err(fail(Reason::Toggle)),
+                    Instruction::In(_) => return Err(fail(Reason::Input)),
+                    Instruction::Out(value) => path.output.push(resolve(&value, &path.registers)),
+                    Instruction::JumpIfNotZero(condition, target) => {
+                        let offset = resolve(&target, &path.registers)
+                            .as_constant()
+                            .ok_or_else(|| fail(Reason::ComputedJump))?;
+                        let condition = resolve(&condition, &path.registers);
+                        let jump = match path.decide(&condition) {
+                            Some(jump) => jump,
+                            None if offset <= 0 => {
+                                return Err(fail(Reason::SymbolicLoop(condition)))
+                            }
+                            None => {
+                                path_count += 1;
+                                if path_count > MAX_PATHS {
+                                    return Err(fail(Reason::PathLimit));
+                                }
+                                let mut taken = path.clone();
+                                taken.assume(Condition::NonZero(condition.clone()).normalized());
+                                taken.ptr += offset;
+                                paths.push(taken);
+                                path.assume(Condition::Zero(condition).normalized());
+                                false
+                            }
+                        };
+                        if jump {
+                            path.ptr += offset;
+                            continue;
+                        }
+                    }
+                    instruction => apply(&instruction, &mut path.registers)
+                        .ok_or_else(|| fail(Reason::Overflow))?,
+                }
+                path.ptr += 1;
+            }
+            cases.push(Case {
+                conditions: path.conditions,
+                registers: path.registers,
+                output: path.output,
+            });
+        }
+        Ok(cases)
+    }
+
+    fn summarize(&mut self, header: usize) -> Option<Summary> {
+        if let Some(summary) = self.summaries.get(&header) {
+            return summary.clone();
+        }
+        // Marks the loop as not summarized while summarizing it
+        self.summaries.insert(header, None);
+        let summary = self.compute_summary(header);
+        self.summaries.insert(header, summary.clone());
+        summary
+    }
+
+    fn compute_summary(&mut self, header: usize) -> Option<Summary> {
+        let (end, counter) =
+            self.instructions
+                .iter()
+                .enumerate()
+                .skip(header + 1)
+                .find_map(|(idx, instruction)| match instruction {
+                    Instruction::JumpIfNotZero(
+                        Value::Register(counter),
+                        Value::Constant(offset),
+                    ) if idx as i64 + offset == header as i64 => Some((idx, *counter)),
+                    _ => None,
+                })?;
+        let start: Vec<Poly> = (0..self.registers).map(Poly::register).collect();
+        let (registers, conditions) = self.body_effect(header, end, start.clone())?;
+
+        let modified: Vec<usize> = (0..self.registers)
+            .filter(|&register| registers[register] != start[register])
+            .collect();
+        let uses_modified = |poly: &Poly| modified.iter().any(|&register| poly.uses(register));
+        let count = match registers[counter]
+            .checked_sub(&start[counter])?
+            .as_constant()?
+        {
+            -1 => start[counter].clone(),
+            1 => start[counter].checked_neg()?,
+            _ => return None,
+        };
+        if conditions
+            .iter()
+            .any(|condition| uses_modified(condition.poly()))
+        {
+            return None;
+        }
+        let mut updates = Vec::new();
+        for register in 0..self.registers {
+            updates.push(if register == counter || !modified.contains(&register) {
+                Update::Keep
+            } else {
+                let delta = registers[register].checked_sub(&start[register])?;
+                if !uses_modified(&delta) {
+                    Update::Add(delta)
+                } else if !uses_modified(&registers[register]) {
+                    Update::Set(registers[register].clone())
+                } else {
+                    return None;
+                }
+            });
+        }
+        Some(Summary {
+            end,
+            counter,
+            count,
+            conditions,
+            updates,
+        })
+    }
+
+    // Runs the instructions from `start` up to `end` without branching, inner
+    // loops have to be summarized
+    fn body_effect(
+        &mut self,
+        start: usize,
+        end: usize,
+        mut registers: Vec<Poly>,
+    ) -> Option<(Vec<Poly>, Vec<Condition>)> {
+        let mut conditions: Vec<Condition> = Vec::new();
+        let mut ptr = start;
+        while ptr < end {
+            if ptr != start {
+                if let Some(summary) = self.summarize(ptr).filter(|summary| summary.end < end) {
+                    for condition in summary.apply(&mut registers)? {
+                        if !conditions.contains(&condition) {
+                            conditions.push(condition);
+                        }
+                    }
+                    ptr = summary.end + 1;
+                    continue;
+                }
+            }
+            match &self.instructions[ptr] {
+                Instruction::JumpIfNotZero(Value::Constant(0), _) => {}
+                Instruction::JumpIfNotZero(..)
+                | Instruction::Toggle(_)
+                | Instruction::Out(_)
+                | Instruction::In(_) => return None,
+                instruction => apply(instruction, &mut registers)?,
+            }
+            ptr += 1;
+        }
+        Some((registers, conditions))
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let range = |range: &Range<usize>| {
+            if range.len() == 1 {
+                range.start.to_string()
+            } else {
+                format!("{}..={}", range.start, range.end - 1)
+            }
+        };
+        writeln!(f, "blocks")?;
+        for (idx, block) in self.cfg.blocks.iter().enumerate() {
+            let successors = block
+                .successors
+                .iter()
+                .map(|successor| match successor {
+                    Successor::Block(block) => block.to_string(),
+                    Successor::Exit => "exit".to_owned(),
+                    Successor::Unknown => "?".to_owned(),
+                })
+                .join(" ");
+            writeln!(
+                f,
+                "  {}: {} -> {}",
+                idx,
+                range(&block.instructions),
+                successors
+            )?;
+        }
+        if !self.loops.is_empty() {
+            writeln!(f, "loops")?;
+            for l in &self.loops {
+                writeln!(
+                    f,
+                    "  header {}: blocks {}",
+                    l.header,
+                    l.blocks.iter().join(" ")
+                )?;
+            }
+        }
+        match &self.exit {
+            Ok(cases) => {
+                write!(f, "registers at exit")?;
+                for case in cases {
+                    let registers = case
+                        .registers
+                        .iter()
+                        .enumerate()
+                        .map(|(register, poly)| format!("{} = {}", Value::Register(register), poly))
+                        .join(", ");
+                    if case.conditions.is_empty() {
+                        write!(f, "\n  always: {}", registers)?;
+                    } else {
+                        write!(
+                            f,
+                            "\n  if {}: {}",
+                            case.conditions.iter().join(" and "),
+                            registers
+                        )?;
+                    }
+                    if !case.output.is_empty() {
+                        write!(f, "\n    out: {}", case.output.iter().join(", "))?;
+                    }
+                }
+                Ok(())
+            }
+            Err(failure) => write!(f, "registers at exit unknown, {}", failure),
+        }
+    }
+}
+
+#[test]
+fn symbolic() -> anyhow::Result<()> {
+    use crate::assembunny::{assemble, Dialect};
+
+    fn analyze_source(source: &str, initial: &[Option<i64>]) -> anyhow::Result<Analysis> {
+        let program =
+            Program::with_dialect(assemble(source, Dialect::EXTENDED)?, Dialect::EXTENDED)?;
+        Ok(analyze(&program, initial))
+    }
+
+    // Fibonacci in the shape of day 12, c selects how many numbers
+    let fibonacci = "\
+cpy 1 a
+cpy 1 b
+cpy 5 d
+jnz c 2
+jnz 1 5
+cpy 7 c
+inc d
+dec c
+jnz c -2
+cpy a c
+inc a
+dec b
+jnz b -2
+cpy c b
+dec d
+jnz d -6
+cpy 13 c
+cpy 14 d
+inc a
+dec d
+jnz d -2
+dec c
+jnz c -5";
+    let analysis = analyze_source(fibonacci, &[None; 4])?;
+    assert_eq!(analysis.cfg.blocks.len(), 11);
+    assert_eq!(analysis.cfg.block_of(9), 4);
+    assert_eq!(analysis.cfg.block_of(14), 6);
+    let loop_headers: Vec<usize> = analysis.loops.iter().map(|l| l.header).collect();
+    assert_eq!(loop_headers, [3, 4, 5, 8, 9]);
+    let cases = analysis.exit.clone()?;
+    assert_eq!(cases.len(), 2);
+    for case in &cases {
+        let c = if case.conditions == [Condition::Zero(Poly::register(2))] {
+            0
+        } else {
+            1
+        };
+        let mut program = Program::new(assemble(fibonacci, Dialect::PUZZLE)?)?;
+        let mut registers = [0, 0, c, 0];
+        program.run_to_end(&mut registers, |_| {});
+        let derived: Vec<i64> = case
+            .registers
+            .iter()
+            .map(|poly| poly.evaluate(&[0, 0, c, 0]))
+            .collect();
+        assert_eq!(derived, registers);
+    }
+    assert_eq!(
+        analysis.to_string().lines().last(),
+        Some("  if c != 0: a = 559, b = 233, c = 0, d = 0")
+    );
+
+    // Nested counted loops multiply, as long as their counts are positive
+    let analysis = analyze_source(
+        "inc a\ncpy b c\ninc a\ndec c\njnz c -2\ndec d\njnz d -5\nadd 3 a\nmul a a",
+        &[Some(0), None, None, None],
+    )?;
+    assert_eq!(
+        analysis.to_string().lines().last(),
+        Some("  if d > 0 and b > 0: a = b^2*d^2 + 8*b*d + 16, b = b, c = 0, d = 0")
+    );
+    // which is not right without them, the loop wraps around instead
+    assert_eq!(
+        analyze_source("dec a\njnz a -1", &[None; 4])?.exit?[0].conditions,
+        [Condition::Positive(Poly::register(0))]
+    );
+
+    for (source, initial, err) in &[
+        (
+            "cpy 2 a\ntgl a\ninc b\ncpy 1 a",
+            [None; 4],
+            "instruction 1 (tgl a): tgl modifies the program",
+        ),
+        (
+            "cpy a b\nmul b b\njnz b -2",
+            [None; 4],
+            "instruction 2 (jnz b -2): whether the loop repeats depends on a^2",
+        ),
+        (
+            "jnz a a",
+            [None; 4],
+            "instruction 0 (jnz a a): the jump offset depends on the initial registers",
+        ),
+        (
+            "inc a\njnz 1 -1",
+            [Some(0); 4],
+            "instruction 0 (inc a): no exit within 1000000 steps",
+        ),
+    ] {
+        let analysis = analyze_source(source, initial)?;
+        assert_eq!(analysis.exit.unwrap_err().to_string(), *err);
+    }
+
+    Ok(())
+}
@@ -2,15 +2,7 @@
 macro_rules! test_parse {
     ($parser:expr, $($input:expr => $output:expr),+$(,)?) => {
         $({
-            let input = $parser($input.trim())
-                .map_err(|err| anyhow!("parse error {:?}", err))
-                .and_then(|(remainder, v)| {
-                    if remainder == "" {
-                        Ok(v)
-                    } else {
-                        Err(anyhow!("input partially parsed, remainder: {:#?}", remainder))
-                    }
-                })?;
+            let input = $crate::parse_error::parse_all(&$input, $parser)?;
             assert_eq!(input, $output);
         })+
     };
@@ -20,15 +12,7 @@ macro_rules! test_parse {
 macro_rules! test_part {
     ($parser: expr, $part:expr, $($input:expr => $output:expr),+$(,)?) => {
         $({
-            let input = $parser($input.trim())
-                .map_err(|err| anyhow!("parse error {:?}", err))
-                .and_then(|(remainder, v)| {
-                    if remainder == "" {
-                        Ok(v)
-                    } else {
-                        Err(anyhow!("input partially parsed, remainder: {:#?}", remainder))
-                    }
-                })?;
+            let input = $crate::parse_error::parse_all(&$input, $parser)?;
             assert_eq!($part(input)?, $output);
         })+
     };
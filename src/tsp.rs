@@ -0,0 +1,197 @@
+use crate::astar::Cost;
+use anyhow::{anyhow, Result};
+
+/// Largest number of points, the solver needs memory for `2^(n-1) * (n-1)`
+/// partial routes.
+pub const MAX_POINTS: usize = 20;
+
+/// A shortest route through all points of a distance matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route<C> {
+    pub cost: C,
+    /// Indices of the points in the order they are visited, beginning with
+    /// the start. Tours do not repeat the start at the end.
+    pub order: Vec<usize>,
+}
+
+/// Shortest path that begins at `start` and visits every point once, ending
+/// at any of them. `distances[from][to]` is the cost of going between two
+/// points, and does not need to be symmetric.
+pub fn shortest_path<C: Cost>(distances: &[Vec<C>], start: usize) -> Result<Route<C>> {
+    held_karp(distances, start, false)
+}
+
+/// Shortest tour that begins at `start`, visits every other point once and
+/// returns to `start`, like [`shortest_path`].
+pub fn shortest_tour<C: Cost>(distances: &[Vec<C>], start: usize) -> Result<Route<C>> {
+    held_karp(distances, start, true)
+}
+
+// Finds the cheapest way to visit each subset of the other points ending at
+// each of them, building on the subsets with one point less, which takes
+// O(2^n * n^2) time instead of the O(n!) of trying every order
+fn held_karp<C: Cost>(distances: &[Vec<C>], start: usize, is_tour: bool) -> Result<Route<C>> {
+    let len = distances.len();
+    if let Some(row) = distances.iter().find(|row| row.len() != len) {
+        return Err(anyhow!(
+            "distance matrix has {} rows but a row of {} distances",
+            len,
+            row.len()
+        ));
+    }
+    if start >= len {
+        return Err(anyhow!(
+            "start {} is out of range, there are {} points",
+            start,
+            len
+        ));
+    }
+    if len > MAX_POINTS {
+        return Err(anyhow!(
+            "{} points are too many, at most {} are supported",
+            len,
+            MAX_POINTS
+        ));
+    }
+
+    let others: Vec<usize> = (0..len).filter(|&point| point != start).collect();
+    let count = others.len();
+    if count == 0 {
+        return Ok(Route {
+            cost: C::zero(),
+            order: vec![start],
+        });
+    }
+    // Indexed by the visited subset of `others` and the index of the last
+    // point in `others`
+    let subsets = 1usize << count;
+    let mut costs: Vec<Option<C>> = vec![None; subsets * count];
+    let mut previous: Vec<Option<usize>> = vec![None; subsets * count];
+    for (last, &point) in others.iter().enumerate() {
+        costs[(1 << last) * count + last] = Some(distances[start][point].clone());
+    }
+    for visited in 1..subsets {
+        for last in (0..count).filter(|&last| visited & (1 << last) != 0) {
+            let cost = match &costs[visited * count + last] {
+                Some(cost) => cost.clone(),
+                None => continue,
+            };
+            for next in (0..count).filter(|&next| visited & (1 << next) == 0) {
+                let idx = (visited | (1 << next)) * count + next;
+                let cost = cost.clone() + distances[others[last]][others[next]].clone();
+                if costs[idx].as_ref().is_none_or(|best| cost < *best) {
+                    costs[idx] = Some(cost);
+                    previous[idx] = Some(last);
+                }
+            }
+        }
+    }
+
+    let all = subsets - 1;
+    let (mut last, cost) = (0..count)
+        .filter_map(|last| {
+            let cost = costs[all * count + last].clone()?;
+            Some(if is_tour {
+                (last, cost + distances[others[last]][start].clone())
+            } else {
+                (last, cost)
+            })
+        })
+        .min_by(|(_, lhs), (_, rhs)| lhs.cmp(rhs))
+        .unwrap();
+
+    let mut order = Vec::with_capacity(len);
+    let mut visited = all;
+    loop {
+        order.push(others[last]);
+        match previous[visited * count + last] {
+            Some(before) => {
+                visited &= !(1 << last);
+                last = before;
+            }
+            None => break,
+        }
+    }
+    order.push(start);
+    order.reverse();
+    Ok(Route { cost, order })
+}
+
+#[test]
+fn tsp() -> Result<()> {
+    use itertools::Itertools;
+
+    // Asymmetric distances, compared against trying every order
+    let mut seed = 7u64;
+    let distances: Vec<Vec<u64>> = (0..7)
+        .map(|_| {
+            (0..7)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (seed >> 33) % 100
+                })
+                .collect()
+        })
+        .collect();
+    let cost = |order: &[usize]| -> u64 {
+        order
+            .windows(2)
+            .map(|pair| distances[pair[0]][pair[1]])
+            .sum()
+    };
+    for start in 0..7 {
+        let others = (0..7).filter(|&point| point != start);
+        let orders = || {
+            others
+                .clone()
+                .permutations(6)
+                .map(|order| std::iter::once(start).chain(order).collect::<Vec<_>>())
+        };
+        let path = shortest_path(&distances, start)?;
+        assert_eq!(path.order[0], start);
+        assert_eq!(path.cost, cost(&path.order));
+        assert_eq!(Some(path.cost), orders().map(|order| cost(&order)).min());
+
+        let tour = shortest_tour(&distances, start)?;
+        let closed = |order: &[usize]| cost(order) + distances[order[6]][start];
+        assert_eq!(tour.cost, closed(&tour.order));
+        assert_eq!(Some(tour.cost), orders().map(|order| closed(&order)).min());
+    }
+
+    // The map of the example of day 24
+    let distances = vec![
+        vec![0, 2, 8, 10, 2],
+        vec![2, 0, 6, 8, 4],
+        vec![8, 6, 0, 2, 10],
+        vec![10, 8, 2, 0, 8],
+        vec![2, 4, 10, 8, 0],
+    ];
+    assert_eq!(
+        shortest_path(&distances, 0)?,
+        Route {
+            cost: 14,
+            order: vec![0, 4, 1, 2, 3]
+        }
+    );
+    assert_eq!(shortest_tour(&distances, 0)?.cost, 20);
+    assert_eq!(
+        shortest_tour(&[vec![5]], 0)?,
+        Route {
+            cost: 0,
+            order: vec![0]
+        }
+    );
+
+    let err = |result: Result<Route<u64>>| result.unwrap_err().to_string();
+    assert_eq!(
+        err(shortest_path(&[vec![0, 1], vec![1]], 0)),
+        "distance matrix has 2 rows but a row of 1 distances"
+    );
+    assert_eq!(
+        err(shortest_path(&[vec![0]], 1)),
+        "start 1 is out of range, there are 1 points"
+    );
+    assert!(shortest_tour(&vec![vec![0u64; 21]; 21], 0).is_err());
+
+    Ok(())
+}
@@ -73,14 +73,9 @@ where
     }
 }
 
-pub auto trait IsNotVec2 {}
-impl<T> !IsNotVec2 for Vec2<T> {}
-pub auto trait NotEq {}
-impl<X> !NotEq for (X, X) {}
-
 impl<T> From<T> for Vec2<T>
 where
-    T: Clone + IsNotVec2,
+    T: Clone,
 {
     fn from(value: T) -> Self {
         Vec2 {
@@ -98,19 +93,32 @@ impl<T> From<(T, T)> for Vec2<T> {
     }
 }
 
-impl<T, U> From<Vec2<U>> for Vec2<T>
-where
-    T: From<U>,
-    (T, U): NotEq,
-{
-    fn from(value: Vec2<U>) -> Self {
-        Vec2 {
-            x: T::from(value.x),
-            y: T::from(value.y),
-        }
-    }
+// Lossless conversions between differently typed vectors. These are spelled
+// out per pair, because a blanket `impl<T: From<U>, U> From<Vec2<U>>` would
+// overlap with `From<T>` and the reflexive `From<Vec2<T>>`.
+macro_rules! impl_from_vec2 {
+    ($from:ty => $($to:ty),+) => {
+        $(
+            impl From<Vec2<$from>> for Vec2<$to> {
+                fn from(value: Vec2<$from>) -> Self {
+                    Vec2 {
+                        x: <$to>::from(value.x),
+                        y: <$to>::from(value.y),
+                    }
+                }
+            }
+        )+
+    };
 }
 
+impl_from_vec2!(u8 => u16, u32, u64, usize, i16, i32, i64, isize);
+impl_from_vec2!(u16 => u32, u64, usize, i32, i64);
+impl_from_vec2!(u32 => u64, i64);
+impl_from_vec2!(i8 => i16, i32, i64, isize);
+impl_from_vec2!(i16 => i32, i64, isize);
+impl_from_vec2!(i32 => i64);
+impl_from_vec2!(f32 => f64);
+
 macro_rules! impl_op {
     ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident) => {
         impl<T> $trait for Vec2<T>
@@ -227,15 +235,13 @@ where
             let x = T::from_str_radix(value.trim(), radix)?;
             if let Some(value) = iter.next() {
                 let y = T::from_str_radix(value.trim(), radix)?;
-                if let None = iter.next() {
+                if iter.next().is_none() {
                     return Ok(Vec2 { x, y });
                 }
             }
         }
 
-        if let Err(e) = T::from_str_radix("", radix) {
-            return Err(e);
-        }
+        T::from_str_radix("", radix)?;
         unreachable!()
     }
 }
@@ -308,7 +314,7 @@ where
         if let Some(first) = self.next() {
             let mut min = first.clone();
             let mut max = first;
-            while let Some(next) = self.next() {
+            for next in self {
                 match next.x.cmp(&min.x) {
                     Ordering::Less => min.x = next.x.clone(),
                     _ => {